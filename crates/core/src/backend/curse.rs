@@ -1,25 +1,28 @@
-use isahc::config::RedirectPolicy;
-use isahc::{prelude::*, HttpClient};
-use once_cell::sync::Lazy;
+use chrono::{DateTime, Duration, Utc};
+use isahc::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
 
-use crate::backend::{Addon, Flavor, Source, Version};
+use crate::backend::{Addon, Flavor, Folder, Source, Version};
+use crate::config::{join_url, Config};
 use crate::error::Error;
+use crate::request;
+use crate::utility::{parse_date, rfc3339};
 
-fn get_flavor_from_game_version_type_id(game_id: i32) -> Flavor {
+fn get_flavor_from_game_version_type_id(game_id: i32) -> Result<Flavor, Error> {
     match game_id {
-        73246 => Flavor::ClassicTbc,
-        67408 => Flavor::ClassicEra,
-        517 => Flavor::Retail,
-        73713 => Flavor::ClassicWotlk,
-        _ => panic!("Unsupported game id {}", game_id),
+        73246 => Ok(Flavor::ClassicTbc),
+        67408 => Ok(Flavor::ClassicEra),
+        517 => Ok(Flavor::Retail),
+        73713 => Ok(Flavor::ClassicWotlk),
+        _ => Err(Error::UnknownGameVersionType(game_id)),
     }
 }
 
 impl From<Package> for Addon {
     fn from(package: Package) -> Self {
-        let package_cloned = package.clone();
-
+        let latest_files = package.latest_files;
+        let id = package.id;
         let files = package
             .latest_files_indexes
             .into_iter()
@@ -27,31 +30,47 @@ impl From<Package> for Addon {
                 (f.release_type == 1 || f.release_type == 2)
                     && f.game_version_type_id.unwrap_or(0) > 0
             })
-            .collect::<Vec<LatestFilesIndexes>>();
-        let files_cloned = files.clone();
+            .filter_map(|f| {
+                // Files for flavors we don't know yet are skipped, rather
+                // than the whole addon.
+                match get_flavor_from_game_version_type_id(f.game_version_type_id.unwrap_or(0)) {
+                    Ok(flavor) => Some((flavor, f)),
+                    Err(error) => {
+                        eprintln!(
+                            "{}: skipping file {} of {}: {}",
+                            Source::Curse,
+                            f.file_id,
+                            id,
+                            error
+                        );
+                        None
+                    }
+                }
+            })
+            .collect::<Vec<(Flavor, LatestFilesIndexes)>>();
 
         let versions = files
-            .into_iter()
-            .filter(|f| {
+            .iter()
+            .filter(|(flavor, f)| {
                 // We only want the newest for each flavor.
-                !files_cloned.iter().any(|b| {
-                    get_flavor_from_game_version_type_id(b.game_version_type_id.unwrap_or(0))
-                        == get_flavor_from_game_version_type_id(f.game_version_type_id.unwrap_or(0))
-                        && b.file_id > f.file_id
-                })
+                !files
+                    .iter()
+                    .any(|(b_flavor, b)| b_flavor == flavor && b.file_id > f.file_id)
             })
-            .map(|file| {
-                let file_date: String = {
-                    let found = package_cloned
-                        .latest_files
-                        .iter()
-                        .find(|&p| p.id == file.file_id as i64);
-                    if let Some(fd) = found {
-                        fd.file_date.to_owned()
-                    } else {
-                        "1971-01-01T01:01:01.01Z".to_string()
-                    }
-                };
+            .map(|(flavor, file)| {
+                let latest_file = latest_files.iter().find(|&p| p.id == file.file_id as i64);
+                let file_date = latest_file.and_then(|p| parse_date(&p.file_date));
+                let folders = latest_file
+                    .map(|p| {
+                        p.modules
+                            .iter()
+                            .map(|m| Folder {
+                                name: m.foldername.clone(),
+                                fingerprint: m.fingerprint,
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
                 let game_version = if !file.game_version.trim().is_empty() {
                     Some(file.game_version.to_owned())
                 } else {
@@ -59,10 +78,13 @@ impl From<Package> for Addon {
                 };
                 Version {
                     game_version,
-                    flavor: get_flavor_from_game_version_type_id(
-                        file.game_version_type_id.unwrap_or(0),
-                    ),
+                    flavor: *flavor,
+                    addon_version: latest_file.map(|p| p.display_name.clone()),
                     date: file_date,
+                    download_url: latest_file.and_then(|p| p.download_url.clone()),
+                    file_name: Some(file.filename.to_owned()),
+                    file_id: Some(file.file_id as i64),
+                    folders,
                 }
             })
             .collect();
@@ -77,7 +99,11 @@ impl From<Package> for Addon {
             summary: package.summary,
             versions,
             categories: package.categories.into_iter().map(|c| c.name).collect(),
+            authors: package.authors.into_iter().map(|a| a.name).collect(),
+            thumbnail_url: package.logo.map(|logo| logo.thumbnail_url),
+            screenshot_urls: package.screenshots.into_iter().map(|s| s.url).collect(),
             source: Source::Curse,
+            project_id: None,
         }
     }
 }
@@ -87,6 +113,18 @@ struct Category {
     name: String,
 }
 
+#[derive(Deserialize, Serialize, Clone, Debug)]
+struct Author {
+    name: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Image {
+    thumbnail_url: String,
+    url: String,
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 struct File {
@@ -130,6 +168,13 @@ struct Package {
     latest_files_indexes: Vec<LatestFilesIndexes>,
     categories: Vec<Category>,
     allow_mod_distribution: bool,
+    #[serde(default)]
+    authors: Vec<Author>,
+    logo: Option<Image>,
+    #[serde(default)]
+    screenshots: Vec<Image>,
+    #[serde(default, with = "rfc3339")]
+    date_modified: Option<DateTime<Utc>>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -148,53 +193,152 @@ struct Links {
     website_url: Option<String>,
 }
 
-const API_KEY: Option<&'static str> = option_env!("CURSE_API_KEY");
+/// `sortField` value for sorting search results by last update.
+const SORT_FIELD_LAST_UPDATED: u32 = 3;
+
+/// Most ids sent in a single request for packages by id.
+const IDS_PER_REQUEST: usize = 500;
 
-fn base_endpoint(page_size: usize, index: usize) -> String {
-    format!(
-        "https://api.curseforge.com/v1/mods/search?gameId=1&pageSize={}&index={}",
-        page_size, index
+fn base_endpoint(config: &Config, page_size: usize, index: usize) -> String {
+    join_url(
+        &config.base_urls.curse,
+        &format!(
+            "v1/mods/search?gameId=1&pageSize={}&index={}",
+            page_size, index
+        ),
     )
 }
-static HTTP_CLIENT: Lazy<HttpClient> = Lazy::new(|| {
-    HttpClient::builder()
-        .redirect_policy(RedirectPolicy::Follow)
-        .max_connections_per_host(6)
-        .build()
-        .unwrap()
-});
-
-pub async fn get_addons() -> Result<Vec<Addon>, Error> {
-    if let Some(api_key) = API_KEY {
-        let mut index: usize = 0;
-        let page_size: usize = 50;
-        let mut number_of_addons = page_size;
-        let mut addons: Vec<Addon> = vec![];
-        while page_size == number_of_addons {
-            let endpoint = base_endpoint(page_size, index);
-            let mut request = isahc::Request::builder().uri(endpoint);
-            request = request.header("x-api-key", api_key);
-            let mut response = HTTP_CLIENT.send_async(request.body(())?).await?;
-            if response.status().is_success() {
-                let packages = response.json::<Packages>().await?;
-                let packages_len = packages.data.len();
-                let partials_addons = packages
-                    .data
-                    .into_iter()
-                    .filter(|p| p.allow_mod_distribution)
-                    .map(Addon::from)
-                    .collect::<Vec<Addon>>();
-
-                addons.extend_from_slice(&partials_addons);
-                number_of_addons = packages_len;
-                index += page_size;
-            } else {
-                panic!("{}", response.status())
-            }
-        }
 
-        Ok(addons)
-    } else {
-        panic!("API Key not provided");
+fn api_key(config: &Config) -> Result<&str, Error> {
+    config
+        .api_keys
+        .curse
+        .as_deref()
+        .ok_or(Error::MissingApiKey(Source::Curse))
+}
+
+/// Fetches a single page of packages.
+async fn get_packages(
+    config: &Config,
+    endpoint: &str,
+    api_key: &str,
+) -> Result<Vec<Package>, Error> {
+    let headers = [("x-api-key", api_key)];
+    let mut response = request::get(Source::Curse, endpoint, &headers, &config.retry).await?;
+    let packages = response.json::<Packages>().await?;
+    Ok(packages.data)
+}
+
+/// Fetches the packages with `ids`. Packages which no longer exist are left
+/// out.
+async fn get_packages_by_id(
+    config: &Config,
+    ids: &[i32],
+    api_key: &str,
+) -> Result<Vec<Package>, Error> {
+    let endpoint = join_url(&config.base_urls.curse, "v1/mods");
+    let headers = [("x-api-key", api_key)];
+    let mut packages = vec![];
+    for ids in ids.chunks(IDS_PER_REQUEST) {
+        let body = serde_json::json!({ "modIds": ids });
+        let mut response =
+            request::post_json(Source::Curse, &endpoint, &headers, &body, &config.retry).await?;
+        packages.extend(response.json::<Packages>().await?.data);
+    }
+    Ok(packages)
+}
+
+/// Converts the packages which allow distribution to `Addon`.
+fn addons_from_packages(packages: Vec<Package>) -> Vec<Addon> {
+    packages
+        .into_iter()
+        .filter(|p| p.allow_mod_distribution)
+        .map(Addon::from)
+        .collect()
+}
+
+pub async fn get_addons(config: &Config) -> Result<Vec<Addon>, Error> {
+    let api_key = api_key(config)?;
+    let mut index: usize = 0;
+    let page_size: usize = 50;
+    let mut number_of_addons = page_size;
+    let mut addons: Vec<Addon> = vec![];
+    while page_size == number_of_addons {
+        let endpoint = base_endpoint(config, page_size, index);
+        let packages = get_packages(config, &endpoint, api_key).await?;
+        number_of_addons = packages.len();
+        addons.extend(addons_from_packages(packages));
+        index += page_size;
+    }
+
+    Ok(addons)
+}
+
+/// Returns the same addons as `get_addons`, using `baseline`, a catalog in
+/// which Curse was fetched at `fetched_at`, to fetch fewer pages. Without
+/// `fetched_at` every package is fetched.
+///
+/// Packages are fetched sorted by last update until one is older than
+/// `fetched_at`, which finds every new package. The other packages of
+/// `baseline` are fetched by id, many per request, so they have current
+/// download counts, and deleted packages are left out.
+pub async fn get_addons_since(
+    config: &Config,
+    baseline: &[Addon],
+    fetched_at: Option<DateTime<Utc>>,
+) -> Result<Vec<Addon>, Error> {
+    let cutoff = match fetched_at {
+        // Overlap a bit, in case packages were modified while the baseline
+        // was fetched.
+        Some(fetched_at) => fetched_at - Duration::days(1),
+        None => return get_addons(config).await,
+    };
+
+    let api_key = api_key(config)?;
+    let mut index: usize = 0;
+    let page_size: usize = 50;
+    let mut fetched: Vec<Package> = vec![];
+    loop {
+        let endpoint = format!(
+            "{}&sortField={}&sortOrder=desc",
+            base_endpoint(config, page_size, index),
+            SORT_FIELD_LAST_UPDATED
+        );
+        let packages = get_packages(config, &endpoint, api_key).await?;
+        let number_of_addons = packages.len();
+        // Packages without a date say nothing about how far we are, so only
+        // a known date can end the crawl.
+        let reached_cutoff = packages
+            .iter()
+            .any(|p| p.date_modified.is_some_and(|date| date < cutoff));
+        fetched.extend(packages);
+        if reached_cutoff || number_of_addons < page_size {
+            break;
+        }
+        index += page_size;
     }
+
+    let fetched_ids = fetched.iter().map(|p| p.id).collect::<HashSet<i32>>();
+    let ids = baseline
+        .iter()
+        .filter(|a| a.source == Source::Curse && !fetched_ids.contains(&a.id))
+        .map(|a| a.id)
+        .collect::<BTreeSet<i32>>()
+        .into_iter()
+        .collect::<Vec<i32>>();
+    fetched.extend(get_packages_by_id(config, &ids, api_key).await?);
+
+    Ok(addons_from_packages(fetched))
+}
+
+#[test]
+fn test_unknown_game_version_type() {
+    assert_eq!(
+        get_flavor_from_game_version_type_id(517).unwrap(),
+        Flavor::Retail
+    );
+    assert!(matches!(
+        get_flavor_from_game_version_type_id(1),
+        Err(Error::UnknownGameVersionType(1))
+    ));
 }
@@ -0,0 +1,379 @@
+use chrono::{DateTime, Utc};
+use futures::future::{join_all, try_join_all};
+use isahc::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{Addon, Flavor, Source, Version};
+use crate::config::{join_url, Config};
+use crate::error::Error;
+use crate::request;
+use crate::utility::rfc3339;
+
+/// Parses a list of repositories, one per line, as either `owner/name` or a
+/// `https://github.com/owner/name` url. Empty lines and lines starting with
+/// `#` are skipped.
+fn parse_repositories(list: &str) -> Result<Vec<(String, String)>, Error> {
+    list.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let path = line
+                .trim_start_matches("https://")
+                .trim_start_matches("http://")
+                .trim_start_matches("www.")
+                .trim_start_matches("github.com/")
+                .trim_end_matches('/')
+                .trim_end_matches(".git");
+            match path.split('/').collect::<Vec<_>>().as_slice() {
+                [owner, name] if !owner.is_empty() && !name.is_empty() => {
+                    Ok((owner.to_string(), name.to_string()))
+                }
+                _ => Err(Error::InvalidRepository(line.to_owned())),
+            }
+        })
+        .collect()
+}
+
+/// Packager flavor names used in `release.json`.
+fn flavor_for_packager_flavor(flavor: &str) -> Option<Flavor> {
+    match flavor {
+        "mainline" => Some(Flavor::Retail),
+        "classic" => Some(Flavor::ClassicEra),
+        "bcc" => Some(Flavor::ClassicTbc),
+        "wrath" => Some(Flavor::ClassicWotlk),
+        _ => None,
+    }
+}
+
+/// Flavor of a `.toc` interface version, eg. `20502` is Burning Crusade.
+fn flavor_for_interface(interface: u32) -> Flavor {
+    match interface / 10000 {
+        1 => Flavor::ClassicEra,
+        2 => Flavor::ClassicTbc,
+        3 => Flavor::ClassicWotlk,
+        _ => Flavor::Retail,
+    }
+}
+
+/// Suffixes of the `.toc` files for a single flavor, eg. `Addon_Wrath.toc`
+/// next to `Addon.toc`.
+const TOC_SUFFIXES: [&str; 7] = [
+    "Mainline", "Vanilla", "TBC", "Wrath", "Classic", "BCC", "WOTLKC",
+];
+
+/// Whether `file` is a `.toc` of the addon `name`, either `<name>.toc` or
+/// one for a single flavor, eg. `<name>_Vanilla.toc` or `<name>-BCC.toc`.
+fn is_toc(name: &str, file: &str) -> bool {
+    let stem = match file.strip_suffix(".toc") {
+        Some(stem) => stem,
+        None => return false,
+    };
+    match stem.strip_prefix(name) {
+        Some("") => true,
+        Some(suffix) => TOC_SUFFIXES.iter().any(|flavor| {
+            suffix
+                .strip_prefix(['_', '-'])
+                .is_some_and(|s| s.eq_ignore_ascii_case(flavor))
+        }),
+        None => false,
+    }
+}
+
+/// Returns the interface versions of the `## Interface:` lines of a `.toc`,
+/// including flavor specific ones such as `## Interface-Classic:`.
+fn parse_toc_interfaces(toc: &str) -> Vec<u32> {
+    toc.lines()
+        .filter_map(|line| {
+            let line = line.trim().trim_start_matches('#').trim();
+            let (key, value) = line.split_once(':')?;
+            let key = key.trim();
+            if key != "Interface" && !key.starts_with("Interface-") {
+                return None;
+            }
+            Some(value.to_owned())
+        })
+        .flat_map(|value| {
+            value
+                .split(',')
+                .filter_map(|v| v.trim().parse::<u32>().ok())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+struct Repository {
+    id: i32,
+    name: String,
+    html_url: String,
+    description: Option<String>,
+    owner: Owner,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+struct Owner {
+    login: String,
+    avatar_url: Option<String>,
+}
+
+/// An entry of a directory listing.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+struct Content {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+struct Release {
+    tag_name: String,
+    #[serde(default, with = "rfc3339")]
+    published_at: Option<DateTime<Utc>>,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+struct Asset {
+    id: i64,
+    name: String,
+    browser_download_url: String,
+    download_count: u64,
+}
+
+/// `release.json`, as written by the BigWigs packager.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+struct ReleaseJson {
+    releases: Vec<ReleaseJsonFile>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+struct ReleaseJsonFile {
+    filename: String,
+    #[serde(default)]
+    nolib: bool,
+    metadata: Vec<ReleaseJsonMetadata>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+struct ReleaseJsonMetadata {
+    flavor: String,
+    interface: u32,
+}
+
+fn version(release: &Release, flavor: Flavor, interface: u32, asset: Option<&Asset>) -> Version {
+    Version {
+        flavor,
+        game_version: Some(interface.to_string()),
+        addon_version: Some(release.tag_name.clone()),
+        date: release.published_at,
+        download_url: asset.map(|a| a.browser_download_url.clone()),
+        file_name: asset.map(|a| a.name.clone()),
+        file_id: asset.map(|a| a.id),
+        folders: vec![],
+    }
+}
+
+fn versions_from_release_json(release: &Release, release_json: ReleaseJson) -> Vec<Version> {
+    release_json
+        .releases
+        .iter()
+        .filter(|file| !file.nolib)
+        .flat_map(|file| {
+            let asset = release.assets.iter().find(|a| a.name == file.filename);
+            file.metadata.iter().filter_map(move |metadata| {
+                let flavor = flavor_for_packager_flavor(&metadata.flavor)?;
+                Some(version(release, flavor, metadata.interface, asset))
+            })
+        })
+        .collect()
+}
+
+fn versions_from_tocs(release: &Release, tocs: &[String]) -> Vec<Version> {
+    let asset = release.assets.iter().find(|a| a.name.ends_with(".zip"));
+    let mut versions: Vec<Version> = vec![];
+    for interface in tocs.iter().flat_map(|toc| parse_toc_interfaces(toc)) {
+        let flavor = flavor_for_interface(interface);
+        if !versions.iter().any(|v| v.flavor == flavor) {
+            versions.push(version(release, flavor, interface, asset));
+        }
+    }
+    versions
+}
+
+struct Client<'a> {
+    config: &'a Config,
+    authorization: Option<String>,
+}
+
+impl Client<'_> {
+    async fn get(
+        &self,
+        path: &str,
+        accept: &str,
+    ) -> Result<isahc::Response<isahc::AsyncBody>, Error> {
+        let url = join_url(&self.config.base_urls.github, path);
+        let mut headers = vec![("Accept", accept)];
+        if let Some(authorization) = self.authorization.as_deref() {
+            headers.push(("Authorization", authorization));
+        }
+        request::get(Source::GitHub, &url, &headers, &self.config.retry).await
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned + Unpin>(
+        &self,
+        path: &str,
+    ) -> Result<T, Error> {
+        let mut response = self.get(path, "application/vnd.github+json").await?;
+        Ok(response.json::<T>().await?)
+    }
+
+    async fn get_addon(&self, owner: &str, name: &str) -> Result<Addon, Error> {
+        let repository: Repository = self.get_json(&format!("repos/{}/{}", owner, name)).await?;
+        let release: Release = self
+            .get_json(&format!("repos/{}/{}/releases/latest", owner, name))
+            .await?;
+
+        // Flavors are read from `release.json` if the release has one,
+        // otherwise from the `.toc` files named after the repository.
+        let versions = match release.assets.iter().find(|a| a.name == "release.json") {
+            Some(asset) => {
+                let path = format!("repos/{}/{}/releases/assets/{}", owner, name, asset.id);
+                let mut response = self.get(&path, "application/octet-stream").await?;
+                let release_json = response.json::<ReleaseJson>().await?;
+                versions_from_release_json(&release, release_json)
+            }
+            None => {
+                let contents: Vec<Content> = self
+                    .get_json(&format!(
+                        "repos/{}/{}/contents?ref={}",
+                        owner, name, release.tag_name
+                    ))
+                    .await?;
+                let paths = contents
+                    .iter()
+                    .filter(|c| c.kind == "file" && is_toc(&repository.name, &c.name))
+                    .map(|toc| {
+                        format!(
+                            "repos/{}/{}/contents/{}?ref={}",
+                            owner, name, toc.name, release.tag_name
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let tocs = try_join_all(paths.iter().map(|path| async move {
+                    let mut response = self.get(path, "application/vnd.github.raw").await?;
+                    Ok::<_, Error>(response.text().await?)
+                }))
+                .await?;
+                versions_from_tocs(&release, &tocs)
+            }
+        };
+
+        Ok(Addon {
+            id: repository.id,
+            name: repository.name,
+            url: repository.html_url,
+            // GitHub only counts downloads per asset, so this is the latest
+            // release only.
+            number_of_downloads: release.assets.iter().map(|a| a.download_count).sum(),
+            summary: repository.description.unwrap_or_default(),
+            versions,
+            categories: vec![],
+            authors: vec![repository.owner.login],
+            thumbnail_url: repository.owner.avatar_url,
+            screenshot_urls: vec![],
+            source: Source::GitHub,
+            project_id: None,
+        })
+    }
+}
+
+/// Fetches the latest release of every repository in
+/// `config.github_repositories`.
+///
+/// Repositories which fail, eg. as they were deleted or have no release, are
+/// reported and skipped. Only if every repository fails is this an error.
+pub async fn get_addons(config: &Config) -> Result<Vec<Addon>, Error> {
+    let repositories = match &config.github_repositories {
+        Some(path) => parse_repositories(&std::fs::read_to_string(path)?)?,
+        None => return Err(Error::MissingRepositories(Source::GitHub)),
+    };
+    let client = Client {
+        config,
+        authorization: config
+            .api_keys
+            .github
+            .as_ref()
+            .map(|token| format!("Bearer {}", token)),
+    };
+
+    let results = join_all(
+        repositories
+            .iter()
+            .map(|(owner, name)| client.get_addon(owner, name)),
+    )
+    .await;
+
+    let mut addons = vec![];
+    let mut last_error = None;
+    for ((owner, name), result) in repositories.iter().zip(results) {
+        match result {
+            Ok(addon) => addons.push(addon),
+            Err(error) => {
+                eprintln!("{}: skipping {}/{}: {}", Source::GitHub, owner, name, error);
+                last_error = Some(error);
+            }
+        }
+    }
+    match last_error {
+        // Eg. a rejected token or an exhausted rate limit.
+        Some(error) if addons.is_empty() => Err(error),
+        _ => Ok(addons),
+    }
+}
+
+#[test]
+fn test_parse_repositories() {
+    let list = "
+        # Comment
+        WeakAuras/WeakAuras2
+        https://github.com/Stanzilla/AdvancedInterfaceOptions/
+
+        https://github.com/someone/addon.git
+    ";
+    assert_eq!(
+        parse_repositories(list).unwrap(),
+        vec![
+            ("WeakAuras".to_owned(), "WeakAuras2".to_owned()),
+            (
+                "Stanzilla".to_owned(),
+                "AdvancedInterfaceOptions".to_owned()
+            ),
+            ("someone".to_owned(), "addon".to_owned()),
+        ]
+    );
+    assert!(matches!(
+        parse_repositories("just-a-name"),
+        Err(Error::InvalidRepository(_))
+    ));
+}
+
+#[test]
+fn test_parse_toc_interfaces() {
+    let toc = "## Interface: 90105, 20502\n## Title: Addon\n## Interface-Classic: 11400\n";
+    assert_eq!(parse_toc_interfaces(toc), vec![90105, 20502, 11400]);
+    assert!(parse_toc_interfaces("## Interfaces: 90105").is_empty());
+    assert_eq!(flavor_for_interface(11400), Flavor::ClassicEra);
+    assert_eq!(flavor_for_interface(30400), Flavor::ClassicWotlk);
+    assert_eq!(flavor_for_interface(100002), Flavor::Retail);
+}
+
+#[test]
+fn test_is_toc() {
+    assert!(is_toc("Addon", "Addon.toc"));
+    assert!(is_toc("Addon", "Addon_Vanilla.toc"));
+    assert!(is_toc("Addon", "Addon-BCC.toc"));
+    assert!(is_toc("Addon", "Addon_Wrath.toc"));
+    assert!(!is_toc("Addon", "Addon_Options.toc"));
+    assert!(!is_toc("Addon", "Other.toc"));
+    assert!(!is_toc("Addon", "Addon.lua"));
+}
@@ -1,17 +1,35 @@
+use chrono::{DateTime, Utc};
+use futures::future::try_join_all;
 use isahc::prelude::*;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 
 use crate::backend::{Addon, Flavor, Source, Version};
+use crate::config::{join_url, Config};
 use crate::error::Error;
+use crate::request;
+use crate::utility::rfc3339;
 
-impl From<(GameVersion, String)> for Version {
-    fn from(pair: (GameVersion, String)) -> Self {
-        let (game_version, date) = pair;
+impl From<(GameVersion, &Release)> for Version {
+    fn from(pair: (GameVersion, &Release)) -> Self {
+        let (game_version, release) = pair;
+        // Releases only have a download url, the file name is its last segment.
+        let file_name = release
+            .download_url
+            .as_deref()
+            .and_then(|url| url.rsplit('/').next())
+            .filter(|name| !name.is_empty())
+            .map(str::to_owned);
         Version {
             flavor: game_version.game_type,
             game_version: Some(game_version.interface),
-            date,
+            addon_version: release.tag_name.clone().or_else(|| release.name.clone()),
+            date: release.published_at,
+            download_url: release.download_url.clone(),
+            file_name,
+            file_id: None,
+            folders: vec![],
         }
     }
 }
@@ -20,17 +38,21 @@ impl From<Package> for Addon {
     fn from(package: Package) -> Self {
         let re = Regex::new(r"<[^>]*>").unwrap();
         let summary = re.replace_all(&package.description, "").to_string();
-        let first_release = package.releases.first();
-        let versions: Vec<Version> = if let Some(release) = first_release {
-            release
-                .clone()
-                .game_versions
-                .into_iter()
-                .map(|gv| Version::from((gv, release.published_at.clone())))
-                .collect::<Vec<Version>>()
-        } else {
-            vec![]
-        };
+        // We only want the newest release for each flavor, which is not
+        // necessarily the newest release overall.
+        let mut versions: Vec<Version> = vec![];
+        for release in package.releases.iter() {
+            for game_version in release.game_versions.iter() {
+                match versions
+                    .iter_mut()
+                    .find(|v| v.flavor == game_version.game_type)
+                {
+                    Some(version) if version.date >= release.published_at => {}
+                    Some(version) => *version = Version::from((game_version.clone(), release)),
+                    None => versions.push(Version::from((game_version.clone(), release))),
+                }
+            }
+        }
         Addon {
             id: package.id,
             name: package.repository_name,
@@ -39,7 +61,12 @@ impl From<Package> for Addon {
             summary,
             versions,
             categories: vec![],
+            authors: package.owner_name.into_iter().collect(),
+            // Hub has no logos, the avatar of the repository owner is used instead.
+            thumbnail_url: package.owner_image_url,
+            screenshot_urls: package.screenshot_urls,
             source: Source::Hub,
+            project_id: None,
         }
     }
 }
@@ -58,9 +85,12 @@ struct GameVersion {
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
 struct Release {
-    // TODO (casperstorm): Uniform all dates across sources.
     // 2021-04-26T22:42:55.958Z
-    published_at: String,
+    #[serde(with = "rfc3339")]
+    published_at: Option<DateTime<Utc>>,
+    tag_name: Option<String>,
+    name: Option<String>,
+    download_url: Option<String>,
     game_versions: Vec<GameVersion>,
 }
 
@@ -71,20 +101,71 @@ struct Package {
     repository_name: String,
     description: String,
     total_download_count: u64,
+    owner_name: Option<String>,
+    owner_image_url: Option<String>,
+    #[serde(default)]
+    screenshot_urls: Vec<String>,
     releases: Vec<Release>,
 }
 
-fn base_endpoint<'a>() -> &'a str {
-    "https://hub.wowup.io/addons/featured/retail?count=1000"
+/// Game types of the Hub listings which are crawled.
+const GAME_TYPES: [&str; 4] = ["retail", "classic", "bcc", "wotlk"];
+
+/// Requested page size. Hub may cap it, so a short page does not mean it
+/// was the last one.
+const PAGE_SIZE: usize = 100;
+/// Most pages fetched for a listing, in case the API never runs out of them.
+const MAX_PAGES: usize = 1000;
+
+fn base_endpoint(config: &Config, game_type: &str, page: usize) -> String {
+    join_url(
+        &config.base_urls.hub,
+        &format!("addons/{}?page={}&limit={}", game_type, page, PAGE_SIZE),
+    )
+}
+
+/// Fetches every page of the listing for `game_type`, until a page without
+/// new packages: an empty one, or one repeating earlier pages.
+async fn get_packages(config: &Config, game_type: &str) -> Result<Vec<Package>, Error> {
+    let mut packages = vec![];
+    let mut ids: HashSet<i32> = HashSet::new();
+    for page in 1..=MAX_PAGES {
+        let endpoint = base_endpoint(config, game_type, page);
+        let mut response = request::get(Source::Hub, &endpoint, &[], &config.retry).await?;
+        let container = response.json::<Container>().await?;
+        let count = packages.len();
+        packages.extend(container.addons.into_iter().filter(|p| ids.insert(p.id)));
+        if packages.len() == count {
+            break;
+        }
+    }
+    Ok(packages)
 }
 
-pub async fn get_addons() -> Result<Vec<Addon>, Error> {
-    let mut response = isahc::get_async(base_endpoint()).await?;
-    let container = response.json::<Container>().await?;
-    let addons = container
-        .addons
-        .into_iter()
-        .map(Addon::from)
-        .collect::<Vec<Addon>>();
-    Ok(addons)
+pub async fn get_addons(config: &Config) -> Result<Vec<Addon>, Error> {
+    let listings = try_join_all(
+        GAME_TYPES
+            .iter()
+            .map(|game_type| get_packages(config, game_type)),
+    )
+    .await?;
+
+    // A package is listed once for every game type it supports, so versions
+    // are merged into a single addon per id.
+    let mut addons: BTreeMap<i32, Addon> = BTreeMap::new();
+    for addon in listings.into_iter().flatten().map(Addon::from) {
+        match addons.get_mut(&addon.id) {
+            Some(existing) => {
+                for version in addon.versions {
+                    if !existing.versions.iter().any(|v| v.flavor == version.flavor) {
+                        existing.versions.push(version);
+                    }
+                }
+            }
+            None => {
+                addons.insert(addon.id, addon);
+            }
+        }
+    }
+    Ok(addons.into_values().collect())
 }
@@ -1,36 +1,110 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::config::Config;
 use crate::error::Error;
+use crate::utility::rfc3339;
 
 pub mod curse;
+pub mod github;
 pub mod hub;
 pub mod tukui;
+pub mod wago;
 pub mod wowinterface;
 
 #[async_trait]
 pub trait Backend {
-    async fn get_addons(&self) -> Result<Vec<Addon>, Error>;
+    async fn get_addons(&self, config: &Config) -> Result<Vec<Addon>, Error>;
+
+    /// Like `get_addons`, using `baseline` (a previous catalog, in which this
+    /// source was fetched at `fetched_at`) to avoid fetching what has not
+    /// changed, where the backend supports it. See the backend for how the
+    /// result can differ from `get_addons`.
+    async fn get_addons_since(
+        &self,
+        config: &Config,
+        baseline: &[Addon],
+        fetched_at: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Addon>, Error>;
 }
 
 #[async_trait]
 impl Backend for Source {
-    async fn get_addons(&self) -> Result<Vec<Addon>, Error> {
+    async fn get_addons(&self, config: &Config) -> Result<Vec<Addon>, Error> {
         match self {
-            Source::Curse => curse::get_addons().await,
-            Source::Tukui => tukui::get_addons().await,
-            Source::WowI => wowinterface::get_addons().await,
-            Source::Hub => hub::get_addons().await,
+            Source::Curse => curse::get_addons(config).await,
+            Source::Tukui => tukui::get_addons(config).await,
+            Source::WowI => wowinterface::get_addons(config).await,
+            Source::Hub => hub::get_addons(config).await,
+            Source::Wago => wago::get_addons(config).await,
+            Source::GitHub => github::get_addons(config).await,
+        }
+    }
+
+    async fn get_addons_since(
+        &self,
+        config: &Config,
+        baseline: &[Addon],
+        fetched_at: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Addon>, Error> {
+        match self {
+            Source::Curse => curse::get_addons_since(config, baseline, fetched_at).await,
+            // Remaining sources serve everything in a few requests, so there
+            // is nothing to gain.
+            Source::Tukui | Source::WowI | Source::Hub | Source::Wago | Source::GitHub => {
+                self.get_addons(config).await
+            }
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
 pub enum Source {
     Curse,
     Tukui,
     WowI,
     Hub,
+    Wago,
+    GitHub,
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Source::Curse => "curse",
+                Source::Tukui => "tukui",
+                Source::WowI => "wowi",
+                Source::Hub => "hub",
+                Source::Wago => "wago",
+                Source::GitHub => "github",
+            }
+        )
+    }
+}
+
+impl std::str::FromStr for Source {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "curse" => Ok(Source::Curse),
+            "tukui" => Ok(Source::Tukui),
+            "wowi" | "wowinterface" => Ok(Source::WowI),
+            "hub" => Ok(Source::Hub),
+            "wago" => Ok(Source::Wago),
+            "github" => Ok(Source::GitHub),
+            _ => Err(Error::UnknownSource(s.to_owned())),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Hash, PartialOrd, Ord)]
@@ -80,6 +154,33 @@ impl std::fmt::Display for Flavor {
 }
 
 impl Flavor {
+    /// Every name `Flavor` deserializes from: serialized names first, then
+    /// aliases. Must be kept in sync with the serde attributes.
+    pub const NAMES: &'static [&'static str] = &[
+        "Retail",
+        "RetailPtr",
+        "RetailBeta",
+        "ClassicEra",
+        "ClassicTbc",
+        "ClassicPtr",
+        "ClassicBeta",
+        "ClassicWotlk",
+        "retail",
+        "wow_retail",
+        "mainline",
+        "classic",
+        "wow_classic",
+        "classic_era",
+        "vanilla",
+        "tbc",
+        "bcc",
+        "wow_burning_crusade",
+        "burningCrusade",
+        "burning_crusade",
+        "wow-wrath-classic",
+        "wotlk",
+    ];
+
     /// Returns `Flavor` which self relates to.
     pub fn base_flavor(self) -> Flavor {
         match self {
@@ -91,15 +192,60 @@ impl Flavor {
     }
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+// Derived schemas ignore serde aliases, which `Flavor` has plenty of.
+impl JsonSchema for Flavor {
+    fn schema_name() -> String {
+        "Flavor".to_owned()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(Flavor::NAMES.iter().map(|name| (*name).into()).collect()),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
 pub struct Version {
     pub flavor: Flavor,
     pub game_version: Option<String>,
-    pub date: String,
+    /// Version of the addon itself, eg. `4.2.1`, as named by the source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub addon_version: Option<String>,
+    /// Serialized as RFC 3339 in UTC, or `null` if the source has no date.
+    #[serde(default, with = "rfc3339")]
+    #[schemars(with = "Option<DateTime<Utc>>")]
+    pub date: Option<DateTime<Utc>>,
+    /// Where the file of this version can be downloaded, if the source says.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    /// Id of the file within its source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_id: Option<i64>,
+    /// Folders installed by this version. Only Curse provides these.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub folders: Vec<Folder>,
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+/// A folder in the `Interface/AddOns` directory.
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct Folder {
+    pub name: String,
+    /// Curse fingerprint of the folder contents, used to match installed
+    /// folders against the catalog.
+    pub fingerprint: i64,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
 pub struct Addon {
+    /// Id within `source`. Tukui numbers its retail and classic addons
+    /// separately, so a Tukui id can be a different addon in another flavor,
+    /// and `(source, id)` is not unique in a catalog.
     pub id: i32,
     pub name: String,
     pub url: String,
@@ -107,5 +253,42 @@ pub struct Addon {
     pub summary: String,
     pub versions: Vec<Version>,
     pub categories: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub screenshot_urls: Vec<String>,
     pub source: Source,
+    /// Shared by addons from different sources which are the same project.
+    /// See `core::matching`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+}
+
+#[test]
+fn test_flavor_names() {
+    for name in Flavor::NAMES {
+        let json = format!("\"{}\"", name);
+        assert!(
+            serde_json::from_str::<Flavor>(&json).is_ok(),
+            "{} is not a flavor",
+            name
+        );
+    }
+
+    // Every serialized name is listed.
+    for flavor in [
+        Flavor::Retail,
+        Flavor::RetailPtr,
+        Flavor::RetailBeta,
+        Flavor::ClassicEra,
+        Flavor::ClassicTbc,
+        Flavor::ClassicPtr,
+        Flavor::ClassicBeta,
+        Flavor::ClassicWotlk,
+    ] {
+        let name = serde_json::to_value(flavor).unwrap();
+        assert!(Flavor::NAMES.contains(&name.as_str().unwrap()));
+    }
 }
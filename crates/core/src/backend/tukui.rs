@@ -1,10 +1,15 @@
+use futures::future::try_join_all;
 use futures::try_join;
 use isahc::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::backend::{Addon, Flavor, Source, Version};
+use crate::config::{join_url, Config};
 use crate::error::Error;
-use crate::utility::{null_to_default, number_and_string_to_i32, number_and_string_to_u64};
+use crate::request;
+use crate::utility::{
+    null_to_default, number_and_string_to_i32, number_and_string_to_u64, parse_date,
+};
 
 impl From<(Package, Flavor)> for Addon {
     fn from(pair: (Package, Flavor)) -> Self {
@@ -18,10 +23,25 @@ impl From<(Package, Flavor)> for Addon {
             versions: vec![Version {
                 flavor,
                 game_version: Some(package.patch),
-                date: package.lastupdate,
+                addon_version: Some(package.version).filter(|v| !v.is_empty()),
+                date: parse_date(&package.lastupdate),
+                download_url: Some(package.url).filter(|url| !url.is_empty()),
+                file_name: None,
+                file_id: None,
+                folders: vec![],
             }],
             categories: vec![package.category],
+            authors: Some(package.author)
+                .filter(|a| !a.is_empty())
+                .into_iter()
+                .collect(),
+            thumbnail_url: None,
+            screenshot_urls: Some(package.screenshot_url)
+                .filter(|url| !url.is_empty())
+                .into_iter()
+                .collect(),
             source: Source::Tukui,
+            project_id: None,
         }
     }
 }
@@ -54,83 +74,79 @@ struct Package {
     web_url: String,
 }
 
-fn base_endpoint<'a>() -> &'a str {
-    "https://www.tukui.org/api.php"
+fn base_endpoint(config: &Config) -> String {
+    join_url(&config.base_urls.tukui, "api.php")
 }
 
-fn endpoint_for_addons(flavor: &Flavor) -> String {
-    let base_endpoint = base_endpoint();
-    match flavor.base_flavor() {
-        Flavor::Retail => {
-            format!("{}?addons=all", base_endpoint)
-        }
-        Flavor::ClassicEra => {
-            format!("{}?classic-addons=all", base_endpoint)
-        }
-        Flavor::ClassicTbc => {
-            format!("{}?classic-tbc-addons=all", base_endpoint)
-        }
-        _ => panic!("Unknown base flavor {}", flavor),
-    }
+/// Query strings of the Tukui endpoints for a flavor.
+struct Endpoints {
+    flavor: Flavor,
+    /// Lists the addons of the flavor.
+    addons: &'static str,
+    /// ElvUI and Tukui, which have endpoints of their own.
+    uis: [&'static str; 2],
 }
 
-fn endpoint_for_tukui() -> String {
-    format!("{}?ui=tukui", base_endpoint())
+const ENDPOINTS: [Endpoints; 4] = [
+    Endpoints {
+        flavor: Flavor::Retail,
+        addons: "addons=all",
+        uis: ["ui=elvui", "ui=tukui"],
+    },
+    Endpoints {
+        flavor: Flavor::ClassicEra,
+        addons: "classic-addons=all",
+        uis: ["classic-addon=2", "classic-addon=1"],
+    },
+    Endpoints {
+        flavor: Flavor::ClassicTbc,
+        addons: "classic-tbc-addons=all",
+        uis: ["classic-tbc-addon=2", "classic-tbc-addon=1"],
+    },
+    Endpoints {
+        flavor: Flavor::ClassicWotlk,
+        addons: "classic-wotlk-addons=all",
+        uis: ["classic-wotlk-addon=2", "classic-wotlk-addon=1"],
+    },
+];
+
+fn endpoint(config: &Config, query: &str) -> String {
+    format!("{}?{}", base_endpoint(config), query)
 }
 
-fn endpoint_for_elvui() -> String {
-    format!("{}?ui=elvui", base_endpoint())
+async fn get_package(config: &Config, query: &str) -> Result<Package, Error> {
+    let endpoint = endpoint(config, query);
+    let mut response = request::get(Source::Tukui, &endpoint, &[], &config.retry).await?;
+    Ok(response.json::<Package>().await?)
 }
 
-pub async fn get_addons() -> Result<Vec<Addon>, Error> {
-    let flavors = vec![Flavor::Retail, Flavor::ClassicEra, Flavor::ClassicTbc];
-    let mut addons: Vec<Addon> = vec![];
-    for flavor in flavors.iter() {
-        match flavor.base_flavor() {
-            // When fetching retail AddOns, we have to get the two main addons;
-            // Elvui & Tukui from two seperate endpoints, and then combine with
-            // the rest.
-            Flavor::Retail => {
-                let elv_res_future = isahc::get_async(endpoint_for_elvui());
-                let tuk_res_future = isahc::get_async(endpoint_for_tukui());
-                let all_res_future = isahc::get_async(endpoint_for_addons(&flavor));
-
-                let (mut elv_res, mut tuk_res, mut all_res) =
-                    try_join!(elv_res_future, tuk_res_future, all_res_future)?;
-
-                let elv_json_future = elv_res.json::<Package>();
-                let tuk_json_future = tuk_res.json::<Package>();
-                let all_json_future = all_res.json::<Vec<Package>>();
-
-                let (elv_package, tuk_package, all_packages) =
-                    try_join!(elv_json_future, tuk_json_future, all_json_future)?;
-
-                let mut packages = vec![];
-                packages.extend(all_packages);
-                packages.push(elv_package);
-                packages.push(tuk_package);
-
-                // Extends addons with `Package` converted to `Addon`.
-                addons.extend(
-                    packages
-                        .into_iter()
-                        .map(|package| Addon::from((package, *flavor)))
-                        .collect::<Vec<Addon>>(),
-                );
-            }
-            _ => {
-                let mut response = isahc::get_async(endpoint_for_addons(&flavor)).await?;
-                let packages = response.json::<Vec<Package>>().await?;
+async fn get_packages(config: &Config, endpoints: &Endpoints) -> Result<Vec<Package>, Error> {
+    let all = async {
+        let endpoint = endpoint(config, endpoints.addons);
+        let mut response = request::get(Source::Tukui, &endpoint, &[], &config.retry).await?;
+        Ok::<_, Error>(response.json::<Vec<Package>>().await?)
+    };
+    let uis = try_join_all(endpoints.uis.iter().map(|query| get_package(config, query)));
+    let (mut packages, uis) = try_join!(all, uis)?;
+
+    // Some listings include ElvUI and Tukui as well, in which case the
+    // dedicated endpoint is preferred.
+    for ui in uis {
+        packages.retain(|p| p.id != ui.id);
+        packages.push(ui);
+    }
+    Ok(packages)
+}
 
-                // Extends addons with `Package` converted to `Addon`.
-                addons.extend(
-                    packages
-                        .into_iter()
-                        .map(|package| Addon::from((package, *flavor)))
-                        .collect::<Vec<Addon>>(),
-                );
-            }
-        }
+pub async fn get_addons(config: &Config) -> Result<Vec<Addon>, Error> {
+    let mut addons: Vec<Addon> = vec![];
+    for endpoints in ENDPOINTS.iter() {
+        let packages = get_packages(config, endpoints).await?;
+        addons.extend(
+            packages
+                .into_iter()
+                .map(|package| Addon::from((package, endpoints.flavor))),
+        );
     }
 
     Ok(addons)
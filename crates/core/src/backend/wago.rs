@@ -0,0 +1,210 @@
+use chrono::{DateTime, Utc};
+use isahc::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::backend::{Addon, Flavor, Source, Version};
+use crate::config::{join_url, Config};
+use crate::error::Error;
+use crate::request;
+use crate::utility::rfc3339;
+
+fn flavor_for_game_type(game_type: &str) -> Option<Flavor> {
+    match game_type {
+        "retail" => Some(Flavor::Retail),
+        "classic" => Some(Flavor::ClassicEra),
+        "bc" | "bcc" => Some(Flavor::ClassicTbc),
+        "wotlk" => Some(Flavor::ClassicWotlk),
+        _ => None,
+    }
+}
+
+/// Wago ids are strings, eg. `aNDmy96o`, while `Addon.id` is an `i32`. The
+/// id is hashed with 32 bit FNV-1a, which is stable between runs. Collisions
+/// are rare, but possible, see `addons_from_packages`.
+fn id_from_str(id: &str) -> i32 {
+    let hash = id.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    });
+    hash as i32
+}
+
+impl From<Package> for Addon {
+    fn from(package: Package) -> Self {
+        let releases = package
+            .releases
+            .iter()
+            .filter(|r| r.release_type == "stable" || r.release_type == "beta")
+            .filter_map(|r| Some((flavor_for_game_type(&r.game_type)?, r)))
+            .collect::<Vec<(Flavor, &Release)>>();
+
+        let versions = releases
+            .iter()
+            .filter(|(flavor, r)| {
+                // We only want the newest for each flavor.
+                !releases
+                    .iter()
+                    .any(|(b_flavor, b)| b_flavor == flavor && b.created_at > r.created_at)
+            })
+            .map(|(flavor, release)| Version {
+                flavor: *flavor,
+                game_version: release.supported_patch.clone(),
+                addon_version: Some(release.label.clone()),
+                date: release.created_at,
+                download_url: release.download_link.clone(),
+                file_name: None,
+                file_id: None,
+                folders: vec![],
+            })
+            .collect();
+
+        Addon {
+            id: id_from_str(&package.id),
+            name: package.display_name,
+            url: package.website_url,
+            number_of_downloads: package.download_count,
+            summary: package.summary,
+            versions,
+            categories: package.categories,
+            authors: package.authors,
+            thumbnail_url: package.thumbnail_image,
+            screenshot_urls: package.screenshots,
+            source: Source::Wago,
+            project_id: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+struct Page {
+    data: Vec<Package>,
+    meta: Meta,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+struct Meta {
+    current_page: u32,
+    last_page: u32,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+struct Package {
+    id: String,
+    display_name: String,
+    #[serde(default)]
+    summary: String,
+    website_url: String,
+    thumbnail_image: Option<String>,
+    #[serde(default)]
+    screenshots: Vec<String>,
+    #[serde(default)]
+    authors: Vec<String>,
+    #[serde(default)]
+    download_count: u64,
+    #[serde(default)]
+    categories: Vec<String>,
+    #[serde(default)]
+    releases: Vec<Release>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+struct Release {
+    label: String,
+    /// `stable`, `beta` or `alpha`.
+    release_type: String,
+    game_type: String,
+    supported_patch: Option<String>,
+    #[serde(default, with = "rfc3339")]
+    created_at: Option<DateTime<Utc>>,
+    download_link: Option<String>,
+}
+
+fn base_endpoint(config: &Config, page: u32) -> String {
+    join_url(
+        &config.base_urls.wago,
+        &format!("api/external/addons?page={}", page),
+    )
+}
+
+/// Converts `packages` to `Addon`. `ids` maps the ids of every package
+/// converted so far to their Wago id. A package whose id is already taken by
+/// another package is reported and skipped, rather than taken for the same
+/// addon. The first one keeps the id.
+fn addons_from_packages(packages: Vec<Package>, ids: &mut HashMap<i32, String>) -> Vec<Addon> {
+    let mut addons = Vec::with_capacity(packages.len());
+    for package in packages {
+        let id = id_from_str(&package.id);
+        match ids.get(&id) {
+            Some(other) if *other != package.id => {
+                eprintln!(
+                    "{}: skipping {}: its id {} is taken by {}",
+                    Source::Wago,
+                    package.id,
+                    id,
+                    other
+                );
+                continue;
+            }
+            _ => {
+                ids.insert(id, package.id.clone());
+            }
+        }
+        addons.push(Addon::from(package));
+    }
+    addons
+}
+
+pub async fn get_addons(config: &Config) -> Result<Vec<Addon>, Error> {
+    let api_key = config
+        .api_keys
+        .wago
+        .as_deref()
+        .ok_or(Error::MissingApiKey(Source::Wago))?;
+    let authorization = format!("Bearer {}", api_key);
+    let headers = [("Authorization", authorization.as_str())];
+
+    let mut page: u32 = 1;
+    let mut addons: Vec<Addon> = vec![];
+    let mut ids: HashMap<i32, String> = HashMap::new();
+    loop {
+        let endpoint = base_endpoint(config, page);
+        let mut response = request::get(Source::Wago, &endpoint, &headers, &config.retry).await?;
+        let body = response.json::<Page>().await?;
+        let is_last = body.data.is_empty() || body.meta.current_page >= body.meta.last_page;
+        addons.extend(addons_from_packages(body.data, &mut ids));
+        if is_last {
+            break;
+        }
+        page += 1;
+    }
+
+    Ok(addons)
+}
+
+#[test]
+fn test_id_from_str() {
+    // Reference values of 32 bit FNV-1a.
+    assert_eq!(id_from_str(""), 0x811c_9dc5_u32 as i32);
+    assert_eq!(id_from_str("a"), 0xe40c_292c_u32 as i32);
+    assert_ne!(id_from_str("aNDmy96o"), id_from_str("aNDmy96p"));
+}
+
+#[test]
+fn test_id_collision() {
+    let package = |id: &str| -> Package {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "display_name": id,
+            "website_url": "",
+        }))
+        .unwrap()
+    };
+    assert_eq!(id_from_str("l9On"), id_from_str("H8aa"));
+
+    let mut ids = HashMap::new();
+    let addons = addons_from_packages(vec![package("l9On"), package("l9On")], &mut ids);
+    assert_eq!(addons.len(), 2);
+    let addons = addons_from_packages(vec![package("H8aa"), package("aNDmy96o")], &mut ids);
+    let names = addons.iter().map(|a| a.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["aNDmy96o"]);
+}
@@ -1,9 +1,12 @@
 use isahc::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
 
 use crate::backend::{Addon, Flavor, Source, Version};
+use crate::config::{join_url, Config};
 use crate::error::Error;
-use crate::utility::{null_to_default, u64_to_string};
+use crate::request;
+use crate::utility::{date_from_millis, null_to_default, number_and_string_to_u64};
 
 impl From<Package> for Addon {
     fn from(package: Package) -> Self {
@@ -22,10 +25,28 @@ impl From<Package> for Addon {
             versions: vec![Version {
                 flavor,
                 game_version: version,
-                date: package.last_update,
+                addon_version: package.version.filter(|v| !v.is_empty()),
+                // A missing date is read as 0, which is not a real update.
+                date: i64::try_from(package.last_update)
+                    .ok()
+                    .filter(|millis| *millis > 0)
+                    .and_then(date_from_millis),
+                // The file list has no download url, only the file id.
+                download_url: None,
+                file_name: None,
+                file_id: Some(package.id as i64),
+                folders: vec![],
             }],
             categories,
+            authors: Some(package.author)
+                .filter(|a| !a.is_empty())
+                .into_iter()
+                .collect(),
+            // The file list has no images.
+            thumbnail_url: None,
+            screenshot_urls: vec![],
             source: Source::WowI,
+            project_id: None,
         }
     }
 }
@@ -37,8 +58,9 @@ struct Package {
     #[serde(deserialize_with = "null_to_default::deserialize")]
     category_id: i32,
     version: Option<String>,
-    #[serde(deserialize_with = "u64_to_string::deserialize")]
-    last_update: String,
+    /// Epoch milliseconds.
+    #[serde(deserialize_with = "number_and_string_to_u64::deserialize")]
+    last_update: u64,
     title: String,
     author: String,
     file_info_uri: String,
@@ -47,8 +69,8 @@ struct Package {
     game_versions: Vec<String>,
 }
 
-fn base_endpoint<'a>() -> &'a str {
-    "https://api.mmoui.com/v4/game/WOW/filelist.json"
+fn base_endpoint(config: &Config) -> String {
+    join_url(&config.base_urls.wowi, "v4/game/WOW/filelist.json")
 }
 
 /// Returns `Flavor` for a category id `i32`.
@@ -157,8 +179,9 @@ fn category_name_for_category_id(id: i32) -> Option<String> {
     }
 }
 
-pub async fn get_addons() -> Result<Vec<Addon>, Error> {
-    let mut response = isahc::get_async(base_endpoint()).await?;
+pub async fn get_addons(config: &Config) -> Result<Vec<Addon>, Error> {
+    let endpoint = base_endpoint(config);
+    let mut response = request::get(Source::WowI, &endpoint, &[], &config.retry).await?;
     let packages = response.json::<Vec<Package>>().await?;
     let addons = packages
         .into_iter()
@@ -0,0 +1,225 @@
+use chrono::{DateTime, Utc};
+use schemars::schema::RootSchema;
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use crate::backend::{Addon, Source};
+use crate::utility::rfc3339;
+
+/// Version of the `Envelope` layout. Bumped on breaking changes.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A catalog with metadata about when and how it was generated.
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct Envelope {
+    pub schema_version: u32,
+    #[serde(with = "rfc3339::required")]
+    #[schemars(with = "DateTime<Utc>")]
+    pub generated_at: DateTime<Utc>,
+    pub tool_version: String,
+    pub sources: BTreeMap<Source, SourceStats>,
+    pub addons: Vec<Addon>,
+}
+
+/// How fetching a single `Source` went.
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct SourceStats {
+    /// Number of addons from this source in the catalog.
+    pub count: usize,
+    #[serde(with = "rfc3339::required")]
+    #[schemars(with = "DateTime<Utc>")]
+    pub fetched_at: DateTime<Utc>,
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Any catalog layout: a bare array of addons, or an `Envelope`.
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+#[serde(untagged)]
+pub enum Catalog {
+    Envelope(Envelope),
+    Addons(Vec<Addon>),
+}
+
+impl Catalog {
+    /// Returns the JSON Schema describing both catalog layouts.
+    pub fn schema() -> RootSchema {
+        schema_for!(Catalog)
+    }
+
+    pub fn addons(&self) -> &[Addon] {
+        match self {
+            Catalog::Envelope(envelope) => &envelope.addons,
+            Catalog::Addons(addons) => addons,
+        }
+    }
+
+    /// Returns when `source` was fetched, if it was fetched successfully.
+    /// Bare arrays of addons do not say.
+    pub fn fetched_at(&self, source: Source) -> Option<DateTime<Utc>> {
+        match self {
+            Catalog::Envelope(envelope) => envelope
+                .sources
+                .get(&source)
+                .filter(|stats| stats.ok)
+                .map(|stats| stats.fetched_at),
+            Catalog::Addons(_) => None,
+        }
+    }
+
+    pub fn into_addons(self) -> Vec<Addon> {
+        match self {
+            Catalog::Envelope(envelope) => envelope.addons,
+            Catalog::Addons(addons) => addons,
+        }
+    }
+}
+
+impl Addon {
+    /// Sorts `versions` by flavor, their folders by name, and sorts and
+    /// de-duplicates `categories`.
+    pub fn normalize(&mut self) {
+        self.versions.sort_by_key(|v| v.flavor);
+        for version in self.versions.iter_mut() {
+            version.folders.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        self.categories.sort();
+        self.categories.dedup();
+    }
+}
+
+/// Normalizes every addon and sorts them by source, then id, so identical
+/// input always serializes to identical output.
+///
+/// Ties are broken by flavor, name and url.
+pub fn normalize(addons: &mut [Addon]) {
+    for addon in addons.iter_mut() {
+        addon.normalize();
+    }
+    addons.sort_by(compare);
+}
+
+/// Order of addons in a normalized catalog. Addons need not be normalized
+/// themselves.
+pub(crate) fn compare(a: &Addon, b: &Addon) -> Ordering {
+    let key = |addon: &Addon| {
+        (
+            addon.source,
+            addon.id,
+            addon.versions.iter().map(|v| v.flavor).min(),
+        )
+    };
+    key(a)
+        .cmp(&key(b))
+        .then_with(|| a.name.cmp(&b.name))
+        .then_with(|| a.url.cmp(&b.url))
+}
+
+#[test]
+fn test_normalize() {
+    use crate::backend::{Flavor, Source, Version};
+
+    let version = |flavor| Version {
+        flavor,
+        game_version: None,
+        addon_version: None,
+        date: None,
+        download_url: None,
+        file_name: None,
+        file_id: None,
+        folders: vec![],
+    };
+    let addon = |source, id, versions: Vec<Version>, categories: Vec<&str>| Addon {
+        id,
+        name: String::new(),
+        url: String::new(),
+        number_of_downloads: 0,
+        summary: String::new(),
+        versions,
+        categories: categories.into_iter().map(str::to_owned).collect(),
+        authors: vec![],
+        thumbnail_url: None,
+        screenshot_urls: vec![],
+        source,
+        project_id: None,
+    };
+
+    let mut addons = vec![
+        addon(Source::Hub, 1, vec![], vec![]),
+        addon(Source::Tukui, 2, vec![version(Flavor::ClassicTbc)], vec![]),
+        addon(
+            Source::Curse,
+            9,
+            vec![version(Flavor::ClassicEra), version(Flavor::Retail)],
+            vec!["b", "a", "b"],
+        ),
+        addon(Source::Tukui, 2, vec![version(Flavor::ClassicEra)], vec![]),
+        addon(Source::Curse, 3, vec![], vec![]),
+    ];
+    normalize(&mut addons);
+
+    let keys = addons
+        .iter()
+        .map(|a| (a.source, a.id, a.versions.first().map(|v| v.flavor)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        keys,
+        vec![
+            (Source::Curse, 3, None),
+            (Source::Curse, 9, Some(Flavor::Retail)),
+            (Source::Tukui, 2, Some(Flavor::ClassicEra)),
+            (Source::Tukui, 2, Some(Flavor::ClassicTbc)),
+            (Source::Hub, 1, None),
+        ]
+    );
+    assert_eq!(addons[1].categories, vec!["a", "b"]);
+}
+
+#[test]
+fn test_read_both_layouts() {
+    let addon = r#"{
+        "id": 1,
+        "name": "WeakAuras",
+        "url": "",
+        "number_of_downloads": 0,
+        "summary": "",
+        "versions": [{ "flavor": "Retail", "game_version": null, "date": null }],
+        "categories": [],
+        "source": "Curse"
+    }"#;
+
+    let bare = format!("[{}]", addon);
+    let catalog = serde_json::from_str::<Catalog>(&bare).unwrap();
+    assert!(matches!(catalog, Catalog::Addons(_)));
+    assert_eq!(catalog.addons().len(), 1);
+
+    let envelope = format!(
+        r#"{{
+            "schema_version": 1,
+            "generated_at": "2021-11-05T12:00:00Z",
+            "tool_version": "0.2.0",
+            "sources": {{
+                "Curse": {{ "count": 1, "fetched_at": "2021-11-05T11:59:00Z", "ok": true }},
+                "Hub": {{ "count": 0, "fetched_at": "2021-11-05T11:58:00Z", "ok": false, "error": "timeout" }}
+            }},
+            "addons": [{}]
+        }}"#,
+        addon
+    );
+    let catalog = serde_json::from_str::<Catalog>(&envelope).unwrap();
+    match &catalog {
+        Catalog::Envelope(envelope) => {
+            assert_eq!(envelope.schema_version, SCHEMA_VERSION);
+            assert!(envelope.sources[&Source::Curse].ok);
+            assert_eq!(
+                envelope.sources[&Source::Hub].error.as_deref(),
+                Some("timeout")
+            );
+        }
+        Catalog::Addons(_) => panic!("expected an envelope"),
+    }
+    assert_eq!(catalog.into_addons()[0].name, "WeakAuras");
+}
@@ -0,0 +1,142 @@
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::request::RetryPolicy;
+
+/// Configuration shared by all backends.
+///
+/// Can be loaded from a TOML file with `Config::load`, eg:
+///
+/// ```toml
+/// [base_urls]
+/// curse = "http://localhost:8080/curse"
+///
+/// [api_keys]
+/// curse = "secret"
+///
+/// [retry]
+/// max_attempts = 3
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub base_urls: BaseUrls,
+    pub api_keys: ApiKeys,
+    /// File listing the repositories fetched by the GitHub backend, one
+    /// `owner/name` per line.
+    pub github_repositories: Option<PathBuf>,
+    pub retry: RetryPolicy,
+}
+
+/// Base url for each backend. Endpoints are built on top of these.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BaseUrls {
+    pub curse: String,
+    pub tukui: String,
+    pub wowi: String,
+    pub hub: String,
+    pub wago: String,
+    pub github: String,
+}
+
+impl Default for BaseUrls {
+    fn default() -> Self {
+        BaseUrls {
+            curse: "https://api.curseforge.com".to_owned(),
+            tukui: "https://www.tukui.org".to_owned(),
+            wowi: "https://api.mmoui.com".to_owned(),
+            hub: "https://hub.wowup.io".to_owned(),
+            wago: "https://addons.wago.io".to_owned(),
+            github: "https://api.github.com".to_owned(),
+        }
+    }
+}
+
+/// API keys for the backends which require one.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ApiKeys {
+    pub curse: Option<String>,
+    pub wago: Option<String>,
+    /// Optional for GitHub, but raises the rate limit.
+    pub github: Option<String>,
+}
+
+// Keys are secrets, so we make sure they never end up in logs.
+impl std::fmt::Debug for ApiKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKeys")
+            .field("curse", &self.curse.as_ref().map(|_| "***"))
+            .field("wago", &self.wago.as_ref().map(|_| "***"))
+            .field("github", &self.github.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+impl Config {
+    /// Loads `Config` from a TOML file. Missing fields use their default.
+    pub fn load(path: impl AsRef<Path>) -> Result<Config, Error> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&content)?)
+    }
+}
+
+/// Joins `base_url` and `path`, making sure there is exactly one `/` between
+/// them.
+pub(crate) fn join_url(base_url: &str, path: &str) -> String {
+    format!(
+        "{}/{}",
+        base_url.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    )
+}
+
+#[test]
+fn test_load_partial_config() {
+    let config: Config = toml::from_str(
+        r#"
+        [base_urls]
+        curse = "http://localhost:8080/"
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.base_urls.curse, "http://localhost:8080/");
+    assert_eq!(config.base_urls.hub, BaseUrls::default().hub);
+    assert_eq!(
+        join_url(&config.base_urls.curse, "/v1/mods/search"),
+        "http://localhost:8080/v1/mods/search"
+    );
+}
+
+#[test]
+fn test_load_retry_policy() {
+    use isahc::http::StatusCode;
+    use std::time::Duration;
+
+    let config: Config = toml::from_str(
+        r#"
+        [retry]
+        base_delay_ms = 100
+        retryable_statuses = [429, 503]
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        config.retry.max_attempts,
+        RetryPolicy::default().max_attempts
+    );
+    assert_eq!(config.retry.base_delay, Duration::from_millis(100));
+    assert_eq!(config.retry.max_delay, RetryPolicy::default().max_delay);
+    assert_eq!(
+        config.retry.retryable_statuses,
+        vec![
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::SERVICE_UNAVAILABLE
+        ]
+    );
+    assert!(toml::from_str::<Config>("[retry]\nretryable_statuses = [1000]").is_err());
+}
@@ -1,3 +1,5 @@
+use crate::backend::{Flavor, Source};
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error(transparent)]
@@ -8,6 +10,29 @@ pub enum Error {
     SerdeJson(#[from] serde_json::Error),
     #[error(transparent)]
     Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+    #[error("no API key was provided for {0}")]
+    MissingApiKey(Source),
+    #[error("no repository list was provided for {0}")]
+    MissingRepositories(Source),
+    // `r#source` keeps thiserror from treating the field as the error source.
+    #[error("{source} responded with {status} for {url}")]
+    HttpStatus {
+        r#source: Source,
+        status: isahc::http::StatusCode,
+        url: String,
+    },
+    #[error("unknown game version type id {0}")]
+    UnknownGameVersionType(i32),
+    #[error("unsupported flavor {0}")]
+    UnsupportedFlavor(Flavor),
+    #[error("unknown source {0}")]
+    UnknownSource(String),
+    #[error("invalid repository {0}, expected owner/name")]
+    InvalidRepository(String),
+    #[error("failed to fetch addons from required sources {0:?}")]
+    RequiredSourcesFailed(Vec<Source>),
     #[error("unknown error")]
     Unknown,
 }
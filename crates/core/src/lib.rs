@@ -1,3 +1,7 @@
 pub mod backend;
+pub mod catalog;
+pub mod config;
 pub mod error;
+pub mod matching;
+pub mod request;
 pub mod utility;
@@ -0,0 +1,395 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::mem;
+
+use crate::backend::{Addon, Source};
+use crate::catalog;
+
+/// Shortest normalized name used for matching. Shorter names are too generic
+/// to say two addons are the same project.
+const MIN_NAME_LENGTH: usize = 4;
+
+/// Something two addons from different sources can have in common. Ordered
+/// from most to least telling, which is the order keys are matched in.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Key {
+    Repository(String),
+    /// A name and one of its authors, which tells apart addons from the same
+    /// source sharing a name.
+    NameByAuthor(String, String),
+    Name(String),
+}
+
+/// Lowercases `name` and strips everything but letters and digits, so
+/// "Details! Damage Meter" and "details-damage-meter" compare equal.
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Returns `host/owner/repository` if `url` points at a source repository.
+fn repository(url: &str) -> Option<String> {
+    let url = url.trim().to_lowercase();
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let rest = rest.strip_prefix("www.").unwrap_or(rest);
+    let mut parts = rest.split('/').filter(|p| !p.is_empty());
+    let host = parts.next()?;
+    if host != "github.com" && host != "gitlab.com" {
+        return None;
+    }
+    let owner = parts.next()?;
+    let name = parts.next()?;
+    let name = name.strip_suffix(".git").unwrap_or(name);
+    Some(format!("{}/{}/{}", host, owner, name))
+}
+
+fn name_key(name: &str) -> Option<Key> {
+    let name = normalize_name(name);
+    if name.chars().count() >= MIN_NAME_LENGTH {
+        Some(Key::Name(name))
+    } else {
+        None
+    }
+}
+
+fn keys(addon: &Addon) -> Vec<Key> {
+    let mut keys = name_key(&addon.name).into_iter().collect::<Vec<_>>();
+    // Folders are often named after the addon on other sources, eg. the
+    // `WeakAuras` folder of "WeakAuras 2". Bundled libraries are skipped, as
+    // they say nothing about which addon this is.
+    let folders = addon
+        .versions
+        .iter()
+        .flat_map(|v| v.folders.iter())
+        .filter(|f| !f.name.to_lowercase().starts_with("lib"))
+        .filter_map(|f| name_key(&f.name));
+    for key in folders {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    if let Some(Key::Name(name)) = name_key(&addon.name) {
+        for author in addon.authors.iter() {
+            let author = normalize_name(author);
+            if !author.is_empty() {
+                keys.push(Key::NameByAuthor(name.clone(), author));
+            }
+        }
+    }
+    if let Some(repository) = repository(&addon.url) {
+        keys.push(Key::Repository(repository));
+    }
+    keys
+}
+
+/// Disjoint groups of addons, by their index in the addons being matched.
+struct Groups {
+    parents: Vec<usize>,
+    /// Sources of every group, by the index of its root.
+    sources: Vec<BTreeSet<Source>>,
+}
+
+impl Groups {
+    fn new(addons: &[&Addon]) -> Groups {
+        Groups {
+            parents: (0..addons.len()).collect(),
+            sources: addons.iter().map(|a| BTreeSet::from([a.source])).collect(),
+        }
+    }
+
+    fn find(&mut self, index: usize) -> usize {
+        let parent = self.parents[index];
+        if parent == index {
+            return index;
+        }
+        let root = self.find(parent);
+        self.parents[index] = root;
+        root
+    }
+
+    /// Joins the groups of `a` and `b`, unless they already have a source in
+    /// common: a project has at most one addon per source.
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b || !self.sources[a].is_disjoint(&self.sources[b]) {
+            return;
+        }
+        let (root, child) = (a.min(b), a.max(b));
+        self.parents[child] = root;
+        let sources = mem::take(&mut self.sources[child]);
+        self.sources[root].extend(sources);
+    }
+}
+
+/// Groups addons from different sources which are the same project, and
+/// sets `project_id` on every addon in a group of two or more sources.
+///
+/// Addons are matched on their repository url, name and author, normalized
+/// name, and folder names. A key shared by two different addons from the
+/// same source is ambiguous, and is not used for matching.
+///
+/// The project id is the lowest `source:id` in the group, eg. `curse:65387`,
+/// so it is stable as long as that addon is in the catalog. Tukui ids are not
+/// unique, so a Tukui addon never gives its id to a project. A group has at
+/// most one Tukui addon, so there is always another one to take it from.
+pub fn assign_projects(addons: &mut [Addon]) {
+    // Addons are matched in catalog order, as which of two conflicting
+    // matches wins depends on the order. Sources return addons in no
+    // particular order, eg. Curse by search ranking.
+    let mut order = (0..addons.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| catalog::compare(&addons[*a], &addons[*b]));
+    let sorted = order.iter().map(|i| &addons[*i]).collect::<Vec<_>>();
+
+    let mut owners: BTreeMap<Key, BTreeSet<usize>> = BTreeMap::new();
+    for (index, addon) in sorted.iter().enumerate() {
+        for key in keys(addon) {
+            owners.entry(key).or_default().insert(index);
+        }
+    }
+
+    let mut groups = Groups::new(&sorted);
+    for indices in owners.values() {
+        let sources = indices
+            .iter()
+            .map(|i| sorted[*i].source)
+            .collect::<BTreeSet<_>>();
+        if sources.len() != indices.len() {
+            continue;
+        }
+        let mut indices = indices.iter();
+        let first = *indices.next().unwrap();
+        for index in indices {
+            groups.union(first, *index);
+        }
+    }
+
+    // The lowest identity of every group becomes its project id.
+    let mut projects: HashMap<usize, (Source, i32)> = HashMap::new();
+    for (index, addon) in sorted.iter().enumerate() {
+        if addon.source == Source::Tukui {
+            continue;
+        }
+        let identity = (addon.source, addon.id);
+        let project = projects.entry(groups.find(index)).or_insert(identity);
+        *project = identity.min(*project);
+    }
+
+    for (index, position) in order.into_iter().enumerate() {
+        let root = groups.find(index);
+        addons[position].project_id = if groups.sources[root].len() > 1 {
+            let (source, id) = projects[&root];
+            Some(format!("{}:{}", source, id))
+        } else {
+            None
+        };
+    }
+}
+
+#[test]
+fn test_assign_projects() {
+    use crate::backend::{Flavor, Folder, Version};
+
+    let addon = |source, id, name: &str, url: &str| Addon {
+        id,
+        name: name.to_owned(),
+        url: url.to_owned(),
+        number_of_downloads: 0,
+        summary: String::new(),
+        versions: vec![],
+        categories: vec![],
+        authors: vec![],
+        thumbnail_url: None,
+        screenshot_urls: vec![],
+        source,
+        project_id: Some("stale".to_owned()),
+    };
+
+    let folder = |name: &str| Folder {
+        name: name.to_owned(),
+        fingerprint: 0,
+    };
+    let mut addons = vec![
+        addon(
+            Source::Hub,
+            7,
+            "Weak Auras",
+            "https://github.com/WeakAuras/WeakAuras2",
+        ),
+        addon(
+            Source::WowI,
+            24910,
+            "WeakAuras",
+            "https://www.wowinterface.com/downloads/info24910",
+        ),
+        addon(
+            Source::Curse,
+            65387,
+            "WeakAuras",
+            "https://www.curseforge.com/wow/addons/weakauras-2",
+        ),
+        // Matched by repository only.
+        addon(
+            Source::Tukui,
+            3,
+            "WA2",
+            "https://github.com/weakauras/weakauras2.git",
+        ),
+        // Two Curse addons share this name, so only the author tells which
+        // one is on WoWInterface.
+        addon(
+            Source::Curse,
+            1,
+            "Bagnon",
+            "https://www.curseforge.com/wow/addons/bagnon",
+        ),
+        addon(
+            Source::Curse,
+            2,
+            "Bagnon",
+            "https://www.curseforge.com/wow/addons/bagnon-2",
+        ),
+        addon(
+            Source::WowI,
+            1,
+            "Bagnon",
+            "https://www.wowinterface.com/downloads/info1",
+        ),
+        // Matched by folder name.
+        addon(
+            Source::Curse,
+            4,
+            "Deadly Boss Mods",
+            "https://www.curseforge.com/wow/addons/deadly-boss-mods",
+        ),
+        addon(
+            Source::Tukui,
+            4,
+            "DBM Core",
+            "https://www.tukui.org/addons.php?id=4",
+        ),
+        // Too short to match.
+        addon(
+            Source::Curse,
+            3,
+            "UI",
+            "https://www.curseforge.com/wow/addons/ui",
+        ),
+        addon(
+            Source::WowI,
+            3,
+            "U.I.",
+            "https://www.wowinterface.com/downloads/info3",
+        ),
+        // Same Tukui id, different addons.
+        addon(
+            Source::Tukui,
+            5,
+            "Masque",
+            "https://www.tukui.org/addons.php?id=5",
+        ),
+        addon(
+            Source::Tukui,
+            5,
+            "ClassicCastbars",
+            "https://www.tukui.org/classic-addons.php?id=5",
+        ),
+        addon(
+            Source::WowI,
+            20,
+            "Masque",
+            "https://www.wowinterface.com/downloads/info20",
+        ),
+        addon(
+            Source::WowI,
+            6,
+            "ClassicCastbars",
+            "https://www.wowinterface.com/downloads/info6",
+        ),
+        // The repository joins the Hub addon to the second Curse addon
+        // first, so the name can't pull in the first one too.
+        addon(
+            Source::Curse,
+            10,
+            "Skada",
+            "https://www.curseforge.com/wow/addons/skada",
+        ),
+        addon(
+            Source::WowI,
+            10,
+            "Skada",
+            "https://www.wowinterface.com/downloads/info10",
+        ),
+        addon(
+            Source::Curse,
+            11,
+            "Skada Damage Meter",
+            "https://github.com/someone/skada",
+        ),
+        addon(Source::Hub, 10, "Skada", "https://github.com/someone/skada"),
+    ];
+    addons[4].authors = vec!["Jaliborc".to_owned()];
+    addons[5].authors = vec!["Someone Else".to_owned()];
+    addons[6].authors = vec!["jaliborc".to_owned()];
+    addons[7].versions = vec![Version {
+        flavor: Flavor::Retail,
+        game_version: None,
+        addon_version: None,
+        date: None,
+        download_url: None,
+        file_name: None,
+        file_id: None,
+        folders: vec![folder("DBM-Core"), folder("LibStub")],
+    }];
+    assign_projects(&mut addons);
+
+    let projects = addons
+        .iter()
+        .map(|a| a.project_id.as_deref())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        projects,
+        vec![
+            Some("curse:65387"),
+            Some("curse:65387"),
+            Some("curse:65387"),
+            Some("curse:65387"),
+            Some("curse:1"),
+            None,
+            Some("curse:1"),
+            Some("curse:4"),
+            Some("curse:4"),
+            None,
+            None,
+            Some("wowi:20"),
+            Some("wowi:6"),
+            Some("wowi:20"),
+            Some("wowi:6"),
+            Some("curse:10"),
+            Some("curse:10"),
+            Some("curse:11"),
+            Some("curse:11"),
+        ]
+    );
+
+    // The same addons in any order give the same projects.
+    for rotation in 0..addons.len() {
+        for reverse in [false, true] {
+            let mut shuffled = addons.clone();
+            shuffled.rotate_left(rotation);
+            if reverse {
+                shuffled.reverse();
+            }
+            assign_projects(&mut shuffled);
+            for addon in shuffled.iter() {
+                let original = addons
+                    .iter()
+                    .find(|a| (a.source, a.id, &a.name) == (addon.source, addon.id, &addon.name))
+                    .unwrap();
+                assert_eq!(addon.project_id, original.project_id, "{}", addon.name);
+            }
+        }
+    }
+}
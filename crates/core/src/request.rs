@@ -0,0 +1,170 @@
+use isahc::config::RedirectPolicy;
+use isahc::http::{header::RETRY_AFTER, Method, StatusCode};
+use isahc::{prelude::*, AsyncBody, HttpClient, Request, Response};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::time::{Duration, SystemTime};
+
+use crate::backend::Source;
+use crate::error::Error;
+use crate::utility::{millis, status_codes};
+
+static HTTP_CLIENT: Lazy<HttpClient> = Lazy::new(|| {
+    HttpClient::builder()
+        .redirect_policy(RedirectPolicy::Follow)
+        .max_connections_per_host(6)
+        .build()
+        .unwrap()
+});
+
+/// Describes how often, and how long between, a request is retried.
+///
+/// In a config file, delays are given in milliseconds and statuses as
+/// numbers, eg. `base_delay_ms = 500` and `retryable_statuses = [429, 503]`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry. Doubled for every following retry.
+    #[serde(rename = "base_delay_ms", with = "millis")]
+    pub base_delay: Duration,
+    /// Upper bound for a single delay, including `Retry-After`.
+    #[serde(rename = "max_delay_ms", with = "millis")]
+    pub max_delay: Duration,
+    /// Statuses which are considered transient and will be retried.
+    #[serde(with = "status_codes")]
+    pub retryable_statuses: Vec<StatusCode>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            retryable_statuses: vec![
+                StatusCode::REQUEST_TIMEOUT,
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the delay before retry number `retry` (starting at 0), using
+    /// exponential backoff with full jitter.
+    fn backoff(&self, retry: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .checked_mul(2u32.saturating_pow(retry))
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        let millis = exponential.as_millis() as u64;
+        Duration::from_millis(fastrand::u64(0..=millis))
+    }
+
+    /// Returns the delay requested by a `Retry-After` header, if any.
+    fn retry_after(&self, response: &Response<AsyncBody>) -> Option<Duration> {
+        let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+        let delay = match value.trim().parse::<u64>() {
+            Ok(seconds) => Duration::from_secs(seconds),
+            Err(_) => httpdate::parse_http_date(value)
+                .ok()?
+                .duration_since(SystemTime::now())
+                .unwrap_or_default(),
+        };
+        Some(delay.min(self.max_delay))
+    }
+}
+
+/// Sends a GET request to `url` with `headers`, retrying transient failures
+/// according to `policy`.
+///
+/// Returns the response once it has a successful status, otherwise an
+/// `Error::HttpStatus` for `source` with the last status received.
+pub async fn get(
+    source: Source,
+    url: &str,
+    headers: &[(&str, &str)],
+    policy: &RetryPolicy,
+) -> Result<Response<AsyncBody>, Error> {
+    send(source, Method::GET, url, headers, Vec::new(), policy).await
+}
+
+/// Like `get`, sending a POST request with `body` as JSON.
+pub async fn post_json(
+    source: Source,
+    url: &str,
+    headers: &[(&str, &str)],
+    body: &serde_json::Value,
+    policy: &RetryPolicy,
+) -> Result<Response<AsyncBody>, Error> {
+    let mut headers = headers.to_vec();
+    headers.push(("Content-Type", "application/json"));
+    let body = serde_json::to_vec(body)?;
+    send(source, Method::POST, url, &headers, body, policy).await
+}
+
+async fn send(
+    source: Source,
+    method: Method,
+    url: &str,
+    headers: &[(&str, &str)],
+    body: Vec<u8>,
+    policy: &RetryPolicy,
+) -> Result<Response<AsyncBody>, Error> {
+    let mut attempt = 1;
+    loop {
+        let mut request = Request::builder().method(method.clone()).uri(url);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+
+        let delay = match HTTP_CLIENT.send_async(request.body(body.clone())?).await {
+            Ok(response) if response.status().is_success() => return Ok(response),
+            Ok(response) => {
+                let status = response.status();
+                if attempt >= policy.max_attempts || !policy.retryable_statuses.contains(&status) {
+                    return Err(Error::HttpStatus {
+                        source,
+                        status,
+                        url: url.to_owned(),
+                    });
+                }
+                policy
+                    .retry_after(&response)
+                    .unwrap_or_else(|| policy.backoff(attempt - 1))
+            }
+            Err(error) => {
+                let transient = error.is_network() || error.is_timeout();
+                if attempt >= policy.max_attempts || !transient {
+                    return Err(error.into());
+                }
+                policy.backoff(attempt - 1)
+            }
+        };
+
+        async_std::task::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+#[test]
+fn test_backoff_is_bounded() {
+    let policy = RetryPolicy {
+        max_attempts: 10,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_secs(1),
+        retryable_statuses: vec![],
+    };
+
+    for retry in 0..40 {
+        let bound = Duration::from_millis(100 * 2u64.saturating_pow(retry)).min(policy.max_delay);
+        assert!(policy.backoff(retry) <= bound);
+    }
+}
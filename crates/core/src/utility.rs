@@ -1,3 +1,5 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
 /// Deserialize a null to default value.
 pub mod null_to_default {
     use serde::{self, Deserialize, Deserializer};
@@ -12,7 +14,7 @@ pub mod null_to_default {
     }
 }
 
-/// Deserialize a `Number` and `String` to `i32`.
+/// Deserialize a `Number`, `String` and `null` to `i32`.
 pub mod number_and_string_to_i32 {
     use serde::{self, de, Deserialize, Deserializer};
     use std::convert::TryFrom;
@@ -26,12 +28,13 @@ pub mod number_and_string_to_i32 {
                     .ok_or_else(|| de::Error::custom(format!("invalid number: {}", num)))?;
                 i32::try_from(num).ok().unwrap_or(0)
             }
+            serde_json::Value::Null => 0,
             _ => return Err(de::Error::custom("wrong type")),
         })
     }
 }
 
-/// Deserialize a `Number` and `String` to `u64`.
+/// Deserialize a `Number`, `String` and `null` to `u64`.
 pub mod number_and_string_to_u64 {
     use serde::{self, de, Deserialize, Deserializer};
 
@@ -41,24 +44,124 @@ pub mod number_and_string_to_u64 {
             serde_json::Value::Number(num) => num
                 .as_u64()
                 .ok_or_else(|| de::Error::custom(format!("Invalid number: {}", num)))?,
+            serde_json::Value::Null => 0,
             _ => return Err(de::Error::custom("wrong type")),
         })
     }
 }
 
-/// Deserialize a `u64` value to `String`.
-pub mod u64_to_string {
+/// Deserialize a number of milliseconds to `Duration`.
+pub mod millis {
+    use serde::{self, Deserialize, Deserializer};
+    use std::time::Duration;
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        Ok(Duration::from_millis(u64::deserialize(deserializer)?))
+    }
+}
+
+/// Deserialize a list of numbers to HTTP status codes.
+pub mod status_codes {
+    use isahc::http::StatusCode;
     use serde::{self, de, Deserialize, Deserializer};
 
-    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
-        Ok(match serde_json::Value::deserialize(deserializer)? {
-            serde_json::Value::Number(num) => {
-                let num = num
-                    .as_u64()
-                    .ok_or_else(|| de::Error::custom(format!("invalid number: {}", num)))?;
-                num.to_string()
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<StatusCode>, D::Error> {
+        Vec::<u16>::deserialize(deserializer)?
+            .into_iter()
+            .map(|status| StatusCode::from_u16(status).map_err(de::Error::custom))
+            .collect()
+    }
+}
+
+/// Parses a date in any of the formats sent by the sources.
+///
+/// Accepts RFC 3339 (`"2021-04-26T22:42:55.958Z"`), date and time without
+/// timezone (`"2019-07-25 17:00:42"`), plain dates (`"2021-11-03"`) and epoch
+/// milliseconds (`"1622059572000"`). Dates without timezone are assumed to be
+/// UTC.
+pub fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+    let date = date.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(date) {
+        return Some(date.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S") {
+        return Some(Utc.from_utc_datetime(&date));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        return Some(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?));
+    }
+    date.parse::<i64>().ok().and_then(date_from_millis)
+}
+
+/// Returns the date for epoch milliseconds, if it is in range.
+pub fn date_from_millis(millis: i64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_millis_opt(millis).single()
+}
+
+/// (De)serialize an optional date as a RFC 3339 UTC string.
+///
+/// Unknown dates are serialized as `null`. Deserializing is lenient and
+/// accepts every format `parse_date` does, so older catalogs can be read.
+pub mod rfc3339 {
+    use chrono::{DateTime, SecondsFormat, Utc};
+    use serde::{self, de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        date: &Option<DateTime<Utc>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match date {
+            Some(date) => {
+                serializer.serialize_str(&date.to_rfc3339_opts(SecondsFormat::AutoSi, true))
             }
-            _ => return Err(de::Error::custom("wrong type")),
-        })
+            None => serializer.serialize_none(),
+        }
     }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DateTime<Utc>>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(date) if !date.trim().is_empty() => super::parse_date(&date)
+                .map(Some)
+                .ok_or_else(|| de::Error::custom(format!("invalid date: {}", date))),
+            _ => Ok(None),
+        }
+    }
+
+    /// (De)serialize a date which is always known.
+    pub mod required {
+        use chrono::{DateTime, Utc};
+        use serde::{self, de, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(
+            date: &DateTime<Utc>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            super::serialize(&Some(*date), serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<DateTime<Utc>, D::Error> {
+            super::deserialize(deserializer)?.ok_or_else(|| de::Error::custom("missing date"))
+        }
+    }
+}
+
+#[test]
+fn test_parse_date() {
+    let expected = Utc.with_ymd_and_hms(2019, 7, 25, 17, 0, 42).unwrap();
+    assert_eq!(parse_date("2019-07-25 17:00:42"), Some(expected));
+    assert_eq!(parse_date("2019-07-25T17:00:42Z"), Some(expected));
+    assert_eq!(parse_date("2019-07-25T19:00:42+02:00"), Some(expected));
+    assert_eq!(parse_date("1564074042000"), Some(expected));
+    assert_eq!(
+        parse_date("2019-07-25"),
+        Utc.with_ymd_and_hms(2019, 7, 25, 0, 0, 0).single()
+    );
+    assert_eq!(parse_date(""), None);
+    assert_eq!(parse_date("yesterday"), None);
 }
@@ -0,0 +1,622 @@
+mod support;
+
+use chrono::{DateTime, Utc};
+use core::backend::{Addon, Backend, Flavor, Source};
+use core::catalog;
+use core::error::Error;
+use core::utility::parse_date;
+use futures::executor::block_on;
+use support::{read_fixture, MockServer, CURSE_PAGE_0, CURSE_PAGE_50};
+
+fn find(addons: &[Addon], id: i32) -> &Addon {
+    addons.iter().find(|a| a.id == id).unwrap()
+}
+
+fn date(date: &str) -> Option<DateTime<Utc>> {
+    Some(parse_date(date).unwrap())
+}
+
+fn flavors(addon: &Addon) -> Vec<Flavor> {
+    let mut flavors = addon.versions.iter().map(|v| v.flavor).collect::<Vec<_>>();
+    flavors.sort();
+    flavors
+}
+
+#[test]
+fn test_curse() {
+    let server = MockServer::start();
+    server.curse();
+    let addons = block_on(Source::Curse.get_addons(&server.config())).unwrap();
+
+    // Two pages, where one package does not allow distribution.
+    assert_eq!(addons.len(), 51);
+    assert!(addons.iter().all(|a| a.source == Source::Curse));
+    assert!(!addons.iter().any(|a| a.id == 90001));
+
+    let received = server.received();
+    assert_eq!(received.len(), 2);
+    assert_eq!(received[0].url, CURSE_PAGE_0);
+    assert_eq!(received[1].url, CURSE_PAGE_50);
+    assert!(received.iter().all(|r| r
+        .headers
+        .iter()
+        .any(|(name, value)| name.eq_ignore_ascii_case("x-api-key") && value == "test-key")));
+
+    let weakauras = find(&addons, 65387);
+    assert_eq!(weakauras.name, "WeakAuras");
+    assert_eq!(
+        weakauras.url,
+        "https://www.curseforge.com/wow/addons/weakauras-2"
+    );
+    assert_eq!(weakauras.number_of_downloads, 198713412);
+    assert_eq!(weakauras.authors, vec!["Stanzilla", "emptyrivers"]);
+    assert_eq!(
+        weakauras.thumbnail_url.as_deref(),
+        Some("https://media.forgecdn.net/avatars/thumbnails/65387/256/256/logo.png")
+    );
+    assert_eq!(
+        weakauras.screenshot_urls,
+        vec!["https://media.forgecdn.net/attachments/65387/screenshot.jpg"]
+    );
+    assert_eq!(weakauras.categories, vec!["Combat", "Buffs & Debuffs"]);
+    assert_eq!(
+        flavors(weakauras),
+        vec![Flavor::Retail, Flavor::ClassicEra, Flavor::ClassicTbc]
+    );
+    // Only the newest file for each flavor is used.
+    let retail = weakauras
+        .versions
+        .iter()
+        .find(|v| v.flavor == Flavor::Retail)
+        .unwrap();
+    assert_eq!(retail.game_version.as_deref(), Some("9.1.5"));
+    assert_eq!(retail.date, date("2021-11-03T16:24:03.017Z"));
+    assert_eq!(retail.file_id, Some(3524201));
+    assert_eq!(retail.addon_version.as_deref(), Some("3.7.10"));
+    assert_eq!(retail.file_name.as_deref(), Some("WeakAuras-3.7.10.zip"));
+    assert_eq!(
+        retail.download_url.as_deref(),
+        Some("https://edge.forgecdn.net/files/3524/201/WeakAuras-3.7.10.zip")
+    );
+    // Folders of the file, with their fingerprints.
+    let folders = retail
+        .folders
+        .iter()
+        .map(|f| (f.name.as_str(), f.fingerprint))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        folders,
+        vec![
+            ("WeakAuras", 536709362),
+            ("WeakAurasModelPaths", 2302715522),
+            ("WeakAurasOptions", 377019844),
+            ("WeakAurasTemplates", 1560033971),
+        ]
+    );
+
+    // Beta files are included, alpha files are not.
+    let details = find(&addons, 61284);
+    let retail = details
+        .versions
+        .iter()
+        .find(|v| v.flavor == Flavor::Retail)
+        .unwrap();
+    assert_eq!(retail.date, date("2021-11-02T09:13:01.2Z"));
+    let tbc = details
+        .versions
+        .iter()
+        .find(|v| v.flavor == Flavor::ClassicTbc)
+        .unwrap();
+    assert_eq!(tbc.game_version, None);
+
+    // The file for an unknown game version type is skipped, not the addon.
+    let dbm = find(&addons, 3358);
+    assert_eq!(flavors(dbm), vec![Flavor::Retail, Flavor::ClassicWotlk]);
+
+    // Url falls back to the slug.
+    let no_website = find(&addons, 90002);
+    assert_eq!(
+        no_website.url,
+        "https://www.curseforge.com/wow/addons/no-website"
+    );
+    assert_eq!(no_website.number_of_downloads, 13);
+
+    // Second page.
+    assert_eq!(find(&addons, 3510).name, "Ace3");
+}
+
+#[test]
+fn test_curse_missing_api_key() {
+    let server = MockServer::start();
+    server.curse();
+    let mut config = server.config();
+    config.api_keys.curse = None;
+
+    let result = block_on(Source::Curse.get_addons(&config));
+    assert!(matches!(result, Err(Error::MissingApiKey(Source::Curse))));
+    assert!(server.received().is_empty());
+}
+
+#[test]
+fn test_curse_retries_transient_status() {
+    let server = MockServer::start();
+    server
+        .respond(CURSE_PAGE_0, 502, "")
+        .respond(CURSE_PAGE_0, 503, "")
+        .curse();
+
+    let addons = block_on(Source::Curse.get_addons(&server.config())).unwrap();
+    assert_eq!(addons.len(), 51);
+    assert_eq!(server.received().len(), 4);
+}
+
+#[test]
+fn test_curse_gives_up_on_status() {
+    let server = MockServer::start();
+    server.respond(CURSE_PAGE_0, 403, "");
+
+    let result = block_on(Source::Curse.get_addons(&server.config()));
+    match result {
+        Err(Error::HttpStatus {
+            source,
+            status,
+            url,
+        }) => {
+            assert_eq!(source, Source::Curse);
+            assert_eq!(status.as_u16(), 403);
+            assert!(url.ends_with("index=0"));
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+    // 403 is not retryable.
+    assert_eq!(server.received().len(), 1);
+}
+
+/// Every package of the Curse search fixtures.
+fn curse_packages() -> Vec<serde_json::Value> {
+    ["curse/search-0.json", "curse/search-50.json"]
+        .iter()
+        .flat_map(|fixture| {
+            let page: serde_json::Value = serde_json::from_str(&read_fixture(fixture)).unwrap();
+            page["data"].as_array().unwrap().clone()
+        })
+        .collect()
+}
+
+#[test]
+fn test_curse_since() {
+    let server = MockServer::start();
+    server.curse().fixture(
+        "/curse/v1/mods/search?gameId=1&pageSize=50&index=0&sortField=3&sortOrder=desc",
+        "curse/search-updated-0.json",
+    );
+    // Packages which were not modified are fetched by id.
+    let unmodified = curse_packages()
+        .into_iter()
+        .filter(|p| p["id"] != 65387 && p["id"] != 61284)
+        .collect::<Vec<_>>();
+    server.respond(
+        "/curse/v1/mods",
+        200,
+        &serde_json::json!({ "data": unmodified }).to_string(),
+    );
+    let config = server.config();
+    let baseline = block_on(Source::Curse.get_addons(&config)).unwrap();
+    let full_requests = server.received().len();
+
+    let fetched_at = date("2022-10-01T00:00:00Z");
+    let addons = block_on(Source::Curse.get_addons_since(&config, &baseline, fetched_at)).unwrap();
+    // A single sorted page, and the remaining packages by id.
+    let received = server.received();
+    assert_eq!(received.len(), full_requests + 2);
+    let body: serde_json::Value = serde_json::from_str(&received.last().unwrap().body).unwrap();
+    assert_eq!(body["modIds"].as_array().unwrap().len(), baseline.len() - 2);
+
+    // Updated and new addons are included, everything else is refreshed.
+    assert_eq!(addons.len(), baseline.len() + 1);
+    let weakauras = find(&addons, 65387);
+    assert_eq!(weakauras.number_of_downloads, 201000000);
+    let retail = weakauras
+        .versions
+        .iter()
+        .find(|v| v.flavor == Flavor::Retail)
+        .unwrap();
+    assert_eq!(retail.game_version.as_deref(), Some("10.0.0"));
+    assert_eq!(find(&addons, 100547).name, "Plater Nameplates");
+    assert_eq!(find(&addons, 3510).name, "Ace3");
+}
+
+#[test]
+fn test_curse_since_matches_full_run() {
+    let server = MockServer::start();
+    server.curse();
+    // The same packages, sorted by last update like the API does. Packages
+    // without a date come first.
+    let mut packages = curse_packages();
+    for package in packages.iter_mut().filter(|p| p["id"] == 90002) {
+        package["dateModified"] = serde_json::Value::Null;
+    }
+    let modified = |p: &serde_json::Value| p["dateModified"].as_str().and_then(parse_date);
+    packages.sort_by_key(|p| (modified(p).is_some(), std::cmp::Reverse(modified(p))));
+    for (page, packages) in packages.chunks(50).enumerate() {
+        server.respond(
+            &format!(
+                "/curse/v1/mods/search?gameId=1&pageSize=50&index={}&sortField=3&sortOrder=desc",
+                page * 50
+            ),
+            200,
+            &serde_json::json!({ "data": packages }).to_string(),
+        );
+    }
+    // Only the first sorted page is crawled, the rest is fetched by id.
+    server.respond(
+        "/curse/v1/mods",
+        200,
+        &serde_json::json!({ "data": &packages[50..] }).to_string(),
+    );
+    let config = server.config();
+    let mut full = block_on(Source::Curse.get_addons(&config)).unwrap();
+    catalog::normalize(&mut full);
+
+    // A baseline which is stale: modified packages are outdated, download
+    // counts have changed since, one package is new and one was deleted.
+    let mut baseline = full.clone();
+    baseline.retain(|a| a.id != 13501);
+    for addon in baseline
+        .iter_mut()
+        .filter(|a| [65387, 3358, 200045].contains(&a.id))
+    {
+        addon.number_of_downloads = 0;
+        addon.versions.clear();
+    }
+    baseline.push(Addon {
+        id: 99999,
+        ..find(&full, 3510).clone()
+    });
+    // Other sources are left alone.
+    baseline.push(Addon {
+        source: Source::Tukui,
+        ..find(&full, 3510).clone()
+    });
+
+    let requests = server.received().len();
+    let fetched_at = date("2021-10-31T00:00:00Z");
+    let mut since =
+        block_on(Source::Curse.get_addons_since(&config, &baseline, fetched_at)).unwrap();
+    catalog::normalize(&mut since);
+    // The package without a date did not end the crawl, the first page
+    // older than the baseline did.
+    let received = server.received();
+    assert_eq!(received.len(), requests + 2);
+    let body: serde_json::Value = serde_json::from_str(&received.last().unwrap().body).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({ "modIds": [99999, 200036, 200045] })
+    );
+    assert_eq!(
+        serde_json::to_value(&since).unwrap(),
+        serde_json::to_value(&full).unwrap()
+    );
+
+    // Without knowing when the baseline was fetched, everything is fetched.
+    let mut since = block_on(Source::Curse.get_addons_since(&config, &baseline, None)).unwrap();
+    catalog::normalize(&mut since);
+    assert_eq!(
+        serde_json::to_value(&since).unwrap(),
+        serde_json::to_value(&full).unwrap()
+    );
+}
+
+#[test]
+fn test_tukui() {
+    let server = MockServer::start();
+    server.tukui();
+    let addons = block_on(Source::Tukui.get_addons(&server.config())).unwrap();
+
+    // 3 retail addons, ElvUI and Tukui for every flavor, and 1 Wrath addon.
+    assert_eq!(addons.len(), 12);
+    assert!(addons.iter().all(|a| a.source == Source::Tukui));
+
+    let retail = addons
+        .iter()
+        .filter(|a| a.versions[0].flavor == Flavor::Retail)
+        .collect::<Vec<_>>();
+    assert_eq!(retail.len(), 5);
+
+    let elvui = retail.iter().find(|a| a.id == -2).unwrap();
+    assert_eq!(elvui.name, "ElvUI");
+    assert_eq!(elvui.number_of_downloads, 0);
+    assert_eq!(elvui.categories, vec!["Full UI Replacements"]);
+
+    let alhana = find(&addons, 42);
+    assert_eq!(alhana.url, "https://www.tukui.org/addons.php?id=42");
+    assert_eq!(alhana.number_of_downloads, 49786);
+    assert_eq!(alhana.authors, vec!["Alhana"]);
+    assert_eq!(
+        alhana.screenshot_urls,
+        vec!["https://www.tukui.org/addons/screens/42.jpg"]
+    );
+    assert_eq!(alhana.versions[0].date, date("2019-07-25T17:00:42Z"));
+    assert_eq!(alhana.versions[0].addon_version.as_deref(), Some("9.12"));
+    assert_eq!(
+        alhana.versions[0].download_url.as_deref(),
+        Some("https://www.tukui.org/addons.php?download=42")
+    );
+
+    let tbc = addons
+        .iter()
+        .filter(|a| a.versions[0].flavor == Flavor::ClassicTbc)
+        .collect::<Vec<_>>();
+    assert_eq!(tbc.len(), 2);
+    assert!(tbc
+        .iter()
+        .all(|a| a.versions[0].game_version.as_deref() == Some("2.5.2")));
+
+    // ElvUI from the dedicated endpoint replaces the one in the listing.
+    let tbc_elvui = tbc.iter().find(|a| a.id == 2).unwrap();
+    assert_eq!(tbc_elvui.versions[0].addon_version.as_deref(), Some("2.34"));
+
+    let wotlk = addons
+        .iter()
+        .filter(|a| a.versions[0].flavor == Flavor::ClassicWotlk)
+        .collect::<Vec<_>>();
+    assert_eq!(wotlk.len(), 3);
+    let windtools = find(&addons, 12);
+    assert_eq!(windtools.versions[0].flavor, Flavor::ClassicWotlk);
+    assert_eq!(windtools.versions[0].game_version.as_deref(), Some("3.4.0"));
+}
+
+#[test]
+fn test_tukui_missing_endpoint() {
+    let server = MockServer::start();
+    server.fixture("/tukui/api.php?addons=all", "tukui/addons.json");
+
+    let result = block_on(Source::Tukui.get_addons(&server.config()));
+    assert!(matches!(result, Err(Error::HttpStatus { status, .. }) if status.as_u16() == 404));
+}
+
+#[test]
+fn test_wowinterface() {
+    let server = MockServer::start();
+    server.wowi();
+    let addons = block_on(Source::WowI.get_addons(&server.config())).unwrap();
+
+    assert_eq!(addons.len(), 5);
+    assert!(addons.iter().all(|a| a.source == Source::WowI));
+
+    let weakauras = find(&addons, 24910);
+    assert_eq!(
+        weakauras.url,
+        "https://www.wowinterface.com/downloads/info24910"
+    );
+    assert_eq!(weakauras.categories, vec!["Data Mods"]);
+    assert_eq!(weakauras.authors, vec!["Mirrored"]);
+    assert_eq!(weakauras.versions.len(), 1);
+    assert_eq!(weakauras.versions[0].flavor, Flavor::Retail);
+    assert_eq!(weakauras.versions[0].game_version.as_deref(), Some("9.1.5"));
+    assert_eq!(weakauras.versions[0].date, date("2021-11-02T18:50:31Z"));
+    assert_eq!(weakauras.versions[0].file_id, Some(24910));
+    assert_eq!(
+        weakauras.versions[0].addon_version.as_deref(),
+        Some("4.2.1")
+    );
+    // Dates are always serialized as RFC 3339 in UTC.
+    let json = serde_json::to_value(&weakauras.versions[0]).unwrap();
+    assert_eq!(json["date"], "2021-11-02T18:50:31Z");
+
+    // Flavor is decided by the category.
+    let classic = find(&addons, 25478);
+    assert_eq!(classic.versions[0].flavor, Flavor::ClassicEra);
+    assert_eq!(classic.versions[0].game_version.as_deref(), Some("1.14.0"));
+    assert_eq!(classic.categories, vec!["Classic"]);
+
+    let tbc = find(&addons, 26000);
+    assert_eq!(tbc.versions[0].flavor, Flavor::ClassicTbc);
+    assert_eq!(tbc.versions[0].game_version, None);
+
+    let unknown = find(&addons, 38);
+    assert!(unknown.categories.is_empty());
+    assert_eq!(unknown.versions[0].flavor, Flavor::Retail);
+    assert_eq!(unknown.versions[0].date, None);
+}
+
+#[test]
+fn test_hub() {
+    let server = MockServer::start();
+    server.hub();
+    let addons = block_on(Source::Hub.get_addons(&server.config())).unwrap();
+
+    // Every page of every game type, with AdvancedInterfaceOptions listed
+    // for both retail and classic.
+    assert_eq!(server.received().len(), 7);
+    assert_eq!(addons.len(), 5);
+    assert!(addons.iter().all(|a| a.source == Source::Hub));
+
+    let aio = find(&addons, 1035);
+    assert_eq!(aio.name, "AdvancedInterfaceOptions");
+    assert_eq!(
+        aio.url,
+        "https://github.com/Stanzilla/AdvancedInterfaceOptions"
+    );
+    // Html is stripped from the description.
+    assert_eq!(
+        aio.summary,
+        "Restores removed interface options and allows you to change CVars."
+    );
+    // Classic is only supported by the older release.
+    assert_eq!(
+        flavors(aio),
+        vec![Flavor::Retail, Flavor::ClassicEra, Flavor::ClassicTbc]
+    );
+    let classic = aio
+        .versions
+        .iter()
+        .find(|v| v.flavor == Flavor::ClassicEra)
+        .unwrap();
+    assert_eq!(classic.date, date("2021-10-01T09:00:00.000Z"));
+    assert_eq!(classic.addon_version.as_deref(), Some("1.6.1"));
+    assert!(aio
+        .versions
+        .iter()
+        .filter(|v| v.flavor != Flavor::ClassicEra)
+        .all(|v| v.date == date("2021-11-01T11:42:55.958Z")));
+    assert!(aio
+        .versions
+        .iter()
+        .filter(|v| v.flavor != Flavor::ClassicEra)
+        .all(|v| v.download_url.as_deref()
+            == Some("https://github.com/x/releases/download/1.6.2/addon-1.6.2.zip")
+            && v.file_name.as_deref() == Some("addon-1.6.2.zip")
+            && v.addon_version.as_deref() == Some("1.6.2")));
+
+    assert_eq!(aio.authors, vec!["Stanzilla"]);
+    assert_eq!(
+        aio.thumbnail_url.as_deref(),
+        Some("https://avatars.githubusercontent.com/u/75278?v=4")
+    );
+
+    let no_release = find(&addons, 3001);
+    assert!(no_release.versions.is_empty());
+
+    // Second page.
+    assert_eq!(find(&addons, 5005).name, "Rematch");
+    // Only in the classic listing.
+    let castbars = find(&addons, 4001);
+    assert_eq!(flavors(castbars), vec![Flavor::ClassicEra]);
+}
+
+#[test]
+fn test_hub_repeated_page() {
+    let server = MockServer::start();
+    // Past the last page, the listing starts over rather than being empty.
+    server
+        .fixture("/hub/addons/retail?page=1&limit=100", "hub/retail-1.json")
+        .fixture("/hub/addons/retail?page=2&limit=100", "hub/retail-2.json")
+        .fixture("/hub/addons/retail?page=3&limit=100", "hub/retail-1.json")
+        .fixture("/hub/addons/classic?page=1&limit=100", "hub/empty.json")
+        .fixture("/hub/addons/bcc?page=1&limit=100", "hub/empty.json")
+        .fixture("/hub/addons/wotlk?page=1&limit=100", "hub/empty.json");
+    let addons = block_on(Source::Hub.get_addons(&server.config())).unwrap();
+
+    // The repeated page ends the listing.
+    assert_eq!(server.received().len(), 6);
+    assert_eq!(addons.len(), 4);
+}
+
+#[test]
+fn test_wago() {
+    let server = MockServer::start();
+    server.wago();
+    let addons = block_on(Source::Wago.get_addons(&server.config())).unwrap();
+
+    assert_eq!(addons.len(), 3);
+    assert!(addons.iter().all(|a| a.source == Source::Wago));
+    let received = server.received();
+    assert_eq!(received.len(), 2);
+    assert!(received.iter().all(|r| r
+        .headers
+        .iter()
+        .any(|(name, value)| name.eq_ignore_ascii_case("authorization")
+            && value == "Bearer test-key")));
+
+    let weakauras = addons.iter().find(|a| a.name == "WeakAuras").unwrap();
+    assert_eq!(weakauras.url, "https://addons.wago.io/addons/weakauras");
+    assert_eq!(weakauras.authors, vec!["Stanzilla", "emptyrivers"]);
+    // Alpha releases and unknown game types are skipped, and only the newest
+    // release for each flavor is used.
+    assert_eq!(flavors(weakauras), vec![Flavor::Retail, Flavor::ClassicTbc]);
+    let retail = weakauras
+        .versions
+        .iter()
+        .find(|v| v.flavor == Flavor::Retail)
+        .unwrap();
+    assert_eq!(retail.addon_version.as_deref(), Some("3.7.10"));
+    assert_eq!(retail.game_version.as_deref(), Some("9.1.5"));
+    assert_eq!(retail.date, date("2021-11-03T16:24:03Z"));
+
+    // String ids are hashed, so they are the same on every run.
+    let details = addons
+        .iter()
+        .find(|a| a.name.starts_with("Details"))
+        .unwrap();
+    assert_eq!(details.versions[0].flavor, Flavor::ClassicEra);
+    let again = block_on(Source::Wago.get_addons(&server.config())).unwrap();
+    assert!(again.iter().any(|a| a.id == details.id));
+}
+
+#[test]
+fn test_wago_missing_api_key() {
+    let server = MockServer::start();
+    let mut config = server.config();
+    config.api_keys.wago = None;
+
+    let result = block_on(Source::Wago.get_addons(&config));
+    assert!(matches!(result, Err(Error::MissingApiKey(Source::Wago))));
+    assert!(server.received().is_empty());
+}
+
+#[test]
+fn test_github() {
+    let server = MockServer::start();
+    server.github();
+    let addons = block_on(Source::GitHub.get_addons(&server.github_config())).unwrap();
+
+    // The missing repository is skipped.
+    assert_eq!(addons.len(), 2);
+    assert!(addons.iter().all(|a| a.source == Source::GitHub));
+
+    // Flavors from `release.json`, where nolib files are skipped.
+    let aio = find(&addons, 24735453);
+    assert_eq!(aio.name, "AdvancedInterfaceOptions");
+    assert_eq!(
+        aio.url,
+        "https://github.com/Stanzilla/AdvancedInterfaceOptions"
+    );
+    assert_eq!(aio.number_of_downloads, 1510);
+    assert_eq!(aio.authors, vec!["Stanzilla"]);
+    assert_eq!(flavors(aio), vec![Flavor::Retail, Flavor::ClassicTbc]);
+    let tbc = aio
+        .versions
+        .iter()
+        .find(|v| v.flavor == Flavor::ClassicTbc)
+        .unwrap();
+    assert_eq!(tbc.game_version.as_deref(), Some("20502"));
+    assert_eq!(tbc.addon_version.as_deref(), Some("1.6.2"));
+    assert_eq!(tbc.date, date("2021-11-01T11:42:55Z"));
+    assert_eq!(
+        tbc.file_name.as_deref(),
+        Some("AdvancedInterfaceOptions-1.6.2-bcc.zip")
+    );
+    assert_eq!(tbc.file_id, Some(5003));
+
+    // Flavors from the `.toc` files.
+    let simple = find(&addons, 31337);
+    assert_eq!(simple.summary, "");
+    assert_eq!(
+        flavors(simple),
+        vec![Flavor::Retail, Flavor::ClassicEra, Flavor::ClassicWotlk]
+    );
+    assert!(simple
+        .versions
+        .iter()
+        .all(|v| v.file_name.as_deref() == Some("SimpleAddon-v1.2.0.zip")));
+}
+
+#[test]
+fn test_github_every_repository_failing() {
+    let server = MockServer::start();
+    let result = block_on(Source::GitHub.get_addons(&server.github_config()));
+    assert!(matches!(result, Err(Error::HttpStatus { status, .. }) if status.as_u16() == 404));
+}
+
+#[test]
+fn test_github_without_repositories() {
+    let server = MockServer::start();
+    server.github();
+    let result = block_on(Source::GitHub.get_addons(&server.config()));
+    assert!(matches!(
+        result,
+        Err(Error::MissingRepositories(Source::GitHub))
+    ));
+    assert!(server.received().is_empty());
+}
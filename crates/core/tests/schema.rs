@@ -0,0 +1,96 @@
+mod support;
+
+use chrono::Utc;
+use core::backend::{Addon, Backend, Source};
+use core::catalog::{self, Catalog, Envelope, SourceStats, SCHEMA_VERSION};
+use core::matching;
+use futures::executor::block_on;
+use jsonschema::JSONSchema;
+use std::collections::BTreeMap;
+use support::MockServer;
+
+/// Generates a catalog from the fixtures of every backend.
+fn generate() -> Vec<Addon> {
+    let server = MockServer::start();
+    server.curse().tukui().wowi().hub().wago().github();
+    let config = server.github_config();
+
+    let sources = [
+        Source::Curse,
+        Source::Tukui,
+        Source::WowI,
+        Source::Hub,
+        Source::Wago,
+        Source::GitHub,
+    ];
+    let mut addons = vec![];
+    for source in sources {
+        addons.extend(block_on(source.get_addons(&config)).unwrap());
+    }
+    catalog::normalize(&mut addons);
+    matching::assign_projects(&mut addons);
+    addons
+}
+
+fn validate(catalog: &serde_json::Value) {
+    let schema = serde_json::to_value(Catalog::schema()).unwrap();
+    let schema = JSONSchema::compile(&schema).unwrap();
+    if let Err(errors) = schema.validate(catalog) {
+        let errors = errors
+            .map(|e| format!("{} at {}", e, e.instance_path))
+            .collect::<Vec<_>>();
+        panic!("catalog does not match schema: {:#?}", errors);
+    };
+}
+
+#[test]
+fn test_generated_catalog_matches_schema() {
+    let addons = generate();
+    assert!(!addons.is_empty());
+    validate(&serde_json::to_value(&addons).unwrap());
+}
+
+#[test]
+fn test_generated_envelope_matches_schema() {
+    let addons = generate();
+    let mut sources = BTreeMap::new();
+    sources.insert(
+        Source::Curse,
+        SourceStats {
+            count: addons.len(),
+            fetched_at: Utc::now(),
+            ok: true,
+            error: None,
+        },
+    );
+    let envelope = Envelope {
+        schema_version: SCHEMA_VERSION,
+        generated_at: Utc::now(),
+        tool_version: "0.2.0".to_owned(),
+        sources,
+        addons,
+    };
+    validate(&serde_json::to_value(&envelope).unwrap());
+}
+
+#[test]
+fn test_schema_rejects_unknown_flavor() {
+    let schema = serde_json::to_value(Catalog::schema()).unwrap();
+    let schema = JSONSchema::compile(&schema).unwrap();
+    let catalog = serde_json::json!([{
+        "id": 1,
+        "name": "WeakAuras",
+        "url": "",
+        "number_of_downloads": 0,
+        "summary": "",
+        "versions": [{ "flavor": "Dragonflight", "game_version": null, "date": null }],
+        "categories": [],
+        "source": "Curse"
+    }]);
+    assert!(!schema.is_valid(&catalog));
+
+    // Aliases are accepted.
+    let mut catalog = catalog;
+    catalog[0]["versions"][0]["flavor"] = "bcc".into();
+    assert!(schema.is_valid(&catalog));
+}
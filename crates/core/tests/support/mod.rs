@@ -0,0 +1,241 @@
+//! A local HTTP stand-in which replays recorded fixtures to the backends.
+
+// Each test crate only uses part of the helpers.
+#![allow(dead_code)]
+
+use core::config::Config;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Response, Server};
+
+pub const CURSE_PAGE_0: &str = "/curse/v1/mods/search?gameId=1&pageSize=50&index=0";
+pub const CURSE_PAGE_50: &str = "/curse/v1/mods/search?gameId=1&pageSize=50&index=50";
+
+type Routes = HashMap<String, VecDeque<(u16, String)>>;
+
+/// A request received by `MockServer`.
+#[derive(Debug, Clone)]
+pub struct Received {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+pub struct MockServer {
+    server: Arc<Server>,
+    routes: Arc<Mutex<Routes>>,
+    received: Arc<Mutex<Vec<Received>>>,
+}
+
+impl MockServer {
+    /// Starts a server on a random local port. Unknown urls respond with 404.
+    pub fn start() -> MockServer {
+        let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+        let routes: Arc<Mutex<Routes>> = Default::default();
+        let received: Arc<Mutex<Vec<Received>>> = Default::default();
+
+        let (thread_server, thread_routes, thread_received) =
+            (server.clone(), routes.clone(), received.clone());
+        thread::spawn(move || {
+            for mut request in thread_server.incoming_requests() {
+                let mut body = String::new();
+                let _ = request.as_reader().read_to_string(&mut body);
+                thread_received.lock().unwrap().push(Received {
+                    url: request.url().to_owned(),
+                    headers: request
+                        .headers()
+                        .iter()
+                        .map(|h| (h.field.to_string(), h.value.to_string()))
+                        .collect(),
+                    body,
+                });
+
+                let (status, body) = {
+                    let mut routes = thread_routes.lock().unwrap();
+                    match routes.get_mut(request.url()) {
+                        // The last response is repeated for every following request.
+                        Some(responses) if responses.len() > 1 => responses.pop_front().unwrap(),
+                        Some(responses) => responses.front().cloned().unwrap(),
+                        None => (404, String::new()),
+                    }
+                };
+                let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+                let response = Response::from_string(body)
+                    .with_status_code(status)
+                    .with_header(content_type);
+                let _ = request.respond(response);
+            }
+        });
+
+        MockServer {
+            server,
+            routes,
+            received,
+        }
+    }
+
+    /// Responds to `url` with the content of `fixture`, relative to `tests/fixtures`.
+    pub fn fixture(&self, url: &str, fixture: &str) -> &Self {
+        self.respond(url, 200, &read_fixture(fixture))
+    }
+
+    /// Queues a response for `url`.
+    pub fn respond(&self, url: &str, status: u16, body: &str) -> &Self {
+        self.routes
+            .lock()
+            .unwrap()
+            .entry(url.to_owned())
+            .or_default()
+            .push_back((status, body.to_owned()));
+        self
+    }
+
+    /// Returns every request received so far.
+    pub fn received(&self) -> Vec<Received> {
+        self.received.lock().unwrap().clone()
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.server.server_addr())
+    }
+
+    /// Returns a `Config` pointing every backend at this server, with each
+    /// source under its own prefix, eg. `/curse`.
+    pub fn config(&self) -> Config {
+        let mut config = Config::default();
+        config.base_urls.curse = format!("{}/curse", self.url());
+        config.base_urls.tukui = format!("{}/tukui", self.url());
+        config.base_urls.wowi = format!("{}/wowi", self.url());
+        config.base_urls.hub = format!("{}/hub", self.url());
+        config.base_urls.wago = format!("{}/wago", self.url());
+        config.base_urls.github = format!("{}/github", self.url());
+        config.api_keys.curse = Some("test-key".to_owned());
+        config.api_keys.wago = Some("test-key".to_owned());
+        config.retry.base_delay = Duration::from_millis(1);
+        config.retry.max_delay = Duration::from_millis(10);
+        config
+    }
+
+    /// Like `config`, with the GitHub repositories served by `github`.
+    pub fn github_config(&self) -> Config {
+        let mut config = self.config();
+        config.github_repositories = Some(fixture_path("github/repositories.txt"));
+        config
+    }
+
+    /// Serves both pages of the Curse search.
+    pub fn curse(&self) -> &Self {
+        self.fixture(CURSE_PAGE_0, "curse/search-0.json")
+            .fixture(CURSE_PAGE_50, "curse/search-50.json")
+    }
+
+    /// Serves the addon lists and UI packages of every Tukui flavor.
+    pub fn tukui(&self) -> &Self {
+        for list in [
+            "addons",
+            "classic-addons",
+            "classic-tbc-addons",
+            "classic-wotlk-addons",
+        ] {
+            self.fixture(
+                &format!("/tukui/api.php?{}=all", list),
+                &format!("tukui/{}.json", list),
+            );
+        }
+        self.fixture("/tukui/api.php?ui=elvui", "tukui/elvui.json")
+            .fixture("/tukui/api.php?ui=tukui", "tukui/tukui.json");
+        for (query, fixture) in [
+            ("classic-addon=2", "classic-elvui"),
+            ("classic-addon=1", "classic-tukui"),
+            ("classic-tbc-addon=2", "classic-tbc-elvui"),
+            ("classic-tbc-addon=1", "classic-tbc-tukui"),
+            ("classic-wotlk-addon=2", "classic-wotlk-elvui"),
+            ("classic-wotlk-addon=1", "classic-wotlk-tukui"),
+        ] {
+            self.fixture(
+                &format!("/tukui/api.php?{}", query),
+                &format!("tukui/{}.json", fixture),
+            );
+        }
+        self
+    }
+
+    /// Serves the WowInterface file list.
+    pub fn wowi(&self) -> &Self {
+        self.fixture("/wowi/v4/game/WOW/filelist.json", "wowi/filelist.json")
+    }
+
+    /// Serves every page of every Hub game type.
+    pub fn hub(&self) -> &Self {
+        self.fixture("/hub/addons/retail?page=1&limit=100", "hub/retail-1.json")
+            .fixture("/hub/addons/retail?page=2&limit=100", "hub/retail-2.json")
+            .fixture("/hub/addons/retail?page=3&limit=100", "hub/empty.json")
+            .fixture("/hub/addons/classic?page=1&limit=100", "hub/classic-1.json")
+            .fixture("/hub/addons/classic?page=2&limit=100", "hub/empty.json")
+            .fixture("/hub/addons/bcc?page=1&limit=100", "hub/empty.json")
+            .fixture("/hub/addons/wotlk?page=1&limit=100", "hub/empty.json")
+    }
+
+    /// Serves both pages of the Wago addon list.
+    pub fn wago(&self) -> &Self {
+        self.fixture("/wago/api/external/addons?page=1", "wago/addons-1.json")
+            .fixture("/wago/api/external/addons?page=2", "wago/addons-2.json")
+    }
+
+    /// Serves the repositories listed in `github/repositories.txt`: one with
+    /// a `release.json`, one with only `.toc` files, and none for the last.
+    pub fn github(&self) -> &Self {
+        self.fixture(
+            "/github/repos/Stanzilla/AdvancedInterfaceOptions",
+            "github/aio-repository.json",
+        )
+        .fixture(
+            "/github/repos/Stanzilla/AdvancedInterfaceOptions/releases/latest",
+            "github/aio-release.json",
+        )
+        .fixture(
+            "/github/repos/Stanzilla/AdvancedInterfaceOptions/releases/assets/5001",
+            "github/aio-release-json.json",
+        )
+        .fixture(
+            "/github/repos/someone/SimpleAddon",
+            "github/simple-repository.json",
+        )
+        .fixture(
+            "/github/repos/someone/SimpleAddon/releases/latest",
+            "github/simple-release.json",
+        )
+        .fixture(
+            "/github/repos/someone/SimpleAddon/contents?ref=v1.2.0",
+            "github/simple-contents.json",
+        )
+        .fixture(
+            "/github/repos/someone/SimpleAddon/contents/SimpleAddon.toc?ref=v1.2.0",
+            "github/SimpleAddon.toc",
+        )
+        .fixture(
+            "/github/repos/someone/SimpleAddon/contents/SimpleAddon_Wrath.toc?ref=v1.2.0",
+            "github/SimpleAddon_Wrath.toc",
+        )
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+    }
+}
+
+pub fn fixture_path(fixture: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(fixture)
+}
+
+pub fn read_fixture(fixture: &str) -> String {
+    std::fs::read_to_string(fixture_path(fixture)).unwrap()
+}
@@ -0,0 +1,129 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// Compression used for pre-compressed catalog artifacts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Brotli,
+    Zstd,
+}
+
+impl Compression {
+    /// Extension appended to the catalog file name.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Brotli => "br",
+            Compression::Zstd => "zst",
+        }
+    }
+
+    /// Value for the `Content-Encoding` header.
+    pub fn content_encoding(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Brotli => "br",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    pub fn compress(self, bytes: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(vec![], flate2::Compression::best());
+                encoder.write_all(bytes)?;
+                encoder.finish()
+            }
+            Compression::Brotli => {
+                let mut compressed = vec![];
+                {
+                    let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, 11, 22);
+                    encoder.write_all(bytes)?;
+                }
+                Ok(compressed)
+            }
+            Compression::Zstd => zstd::encode_all(bytes, 19),
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gzip" | "gz" => Ok(Compression::Gzip),
+            "br" | "brotli" => Ok(Compression::Brotli),
+            "zstd" | "zst" => Ok(Compression::Zstd),
+            _ => Err(format!("unknown compression {}", s)),
+        }
+    }
+}
+
+/// A file written by the catalog command.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Artifact {
+    pub file: String,
+    pub content_encoding: Option<&'static str>,
+    pub size: usize,
+    pub sha256: String,
+}
+
+impl Artifact {
+    pub fn new(file: String, content_encoding: Option<&'static str>, bytes: &[u8]) -> Artifact {
+        let sha256 = Sha256::digest(bytes)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        Artifact {
+            file,
+            content_encoding,
+            size: bytes.len(),
+            sha256,
+        }
+    }
+}
+
+/// Lists every artifact, so a CDN can serve them with the correct headers.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Manifest {
+    pub artifacts: Vec<Artifact>,
+}
+
+#[test]
+fn test_compress_round_trip() {
+    use std::io::Read;
+
+    let json = br#"[{"id":1,"name":"WeakAuras"}]"#.repeat(100);
+
+    let gzip = Compression::Gzip.compress(&json).unwrap();
+    let mut decoded = vec![];
+    flate2::read::GzDecoder::new(&gzip[..])
+        .read_to_end(&mut decoded)
+        .unwrap();
+    assert_eq!(decoded, json);
+
+    let brotli = Compression::Brotli.compress(&json).unwrap();
+    let mut decoded = vec![];
+    brotli::Decompressor::new(&brotli[..], 4096)
+        .read_to_end(&mut decoded)
+        .unwrap();
+    assert_eq!(decoded, json);
+
+    let zstd = Compression::Zstd.compress(&json).unwrap();
+    assert_eq!(zstd::decode_all(&zstd[..]).unwrap(), json);
+}
+
+#[test]
+fn test_artifact_sha256() {
+    let artifact = Artifact::new("catalog.json".to_owned(), None, b"abc");
+    assert_eq!(artifact.size, 3);
+    assert_eq!(
+        artifact.sha256,
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+}
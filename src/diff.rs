@@ -0,0 +1,376 @@
+use core::backend::{Addon, Flavor, Source, Version};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// An addon in a catalog, identified by `(Source, id)`. Entries with the
+/// same key are combined.
+struct Entry<'a> {
+    name: &'a str,
+    downloads: u64,
+    versions: BTreeMap<Flavor, &'a Version>,
+}
+
+fn entries(addons: &[Addon]) -> BTreeMap<(Source, i32), Entry<'_>> {
+    let mut entries: BTreeMap<(Source, i32), Entry> = BTreeMap::new();
+    for addon in addons {
+        let entry = entries.entry((addon.source, addon.id)).or_insert(Entry {
+            name: &addon.name,
+            downloads: 0,
+            versions: BTreeMap::new(),
+        });
+        entry.downloads += addon.number_of_downloads;
+        for version in addon.versions.iter() {
+            entry.versions.entry(version.flavor).or_insert(version);
+        }
+    }
+    entries
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VersionChange {
+    pub flavor: Flavor,
+    pub old_game_version: Option<String>,
+    pub new_game_version: Option<String>,
+    pub old_addon_version: Option<String>,
+    pub new_addon_version: Option<String>,
+    pub old_date: Option<String>,
+    pub new_date: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    Added {
+        source: Source,
+        id: i32,
+        name: String,
+        flavors: Vec<Flavor>,
+    },
+    Removed {
+        source: Source,
+        id: i32,
+        name: String,
+    },
+    Updated {
+        source: Source,
+        id: i32,
+        name: String,
+        added_flavors: Vec<Flavor>,
+        removed_flavors: Vec<Flavor>,
+        versions: Vec<VersionChange>,
+        downloads_delta: i64,
+    },
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct Summary {
+    pub added: usize,
+    pub removed: usize,
+    pub updated: usize,
+    pub new_flavors: usize,
+    pub version_bumps: usize,
+    pub downloads_delta: i64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct Diff {
+    pub summary: Summary,
+    pub changes: Vec<Change>,
+}
+
+fn format_date(version: &Version) -> Option<String> {
+    version.date.map(|date| date.to_rfc3339())
+}
+
+/// Compares two catalogs, returning every added, removed and updated addon.
+pub fn diff(old: &[Addon], new: &[Addon]) -> Diff {
+    let old = entries(old);
+    let new = entries(new);
+    let keys = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+
+    let mut diff = Diff::default();
+    for key in keys {
+        let (source, id) = *key;
+        let change = match (old.get(key), new.get(key)) {
+            (None, Some(new)) => Change::Added {
+                source,
+                id,
+                name: new.name.to_owned(),
+                flavors: new.versions.keys().copied().collect(),
+            },
+            (Some(old), None) => Change::Removed {
+                source,
+                id,
+                name: old.name.to_owned(),
+            },
+            (Some(old), Some(new)) => {
+                let added_flavors = new
+                    .versions
+                    .keys()
+                    .filter(|f| !old.versions.contains_key(f))
+                    .copied()
+                    .collect::<Vec<_>>();
+                let removed_flavors = old
+                    .versions
+                    .keys()
+                    .filter(|f| !new.versions.contains_key(f))
+                    .copied()
+                    .collect::<Vec<_>>();
+                let versions = new
+                    .versions
+                    .iter()
+                    .filter_map(|(flavor, new)| {
+                        let old = old.versions.get(flavor)?;
+                        if old.game_version == new.game_version
+                            && old.addon_version == new.addon_version
+                            && old.date == new.date
+                        {
+                            return None;
+                        }
+                        Some(VersionChange {
+                            flavor: *flavor,
+                            old_game_version: old.game_version.clone(),
+                            new_game_version: new.game_version.clone(),
+                            old_addon_version: old.addon_version.clone(),
+                            new_addon_version: new.addon_version.clone(),
+                            old_date: format_date(old),
+                            new_date: format_date(new),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                let downloads_delta = new.downloads as i64 - old.downloads as i64;
+
+                if added_flavors.is_empty()
+                    && removed_flavors.is_empty()
+                    && versions.is_empty()
+                    && downloads_delta == 0
+                {
+                    continue;
+                }
+                Change::Updated {
+                    source,
+                    id,
+                    name: new.name.to_owned(),
+                    added_flavors,
+                    removed_flavors,
+                    versions,
+                    downloads_delta,
+                }
+            }
+            (None, None) => continue,
+        };
+
+        let summary = &mut diff.summary;
+        match &change {
+            Change::Added { .. } => summary.added += 1,
+            Change::Removed { .. } => summary.removed += 1,
+            Change::Updated {
+                added_flavors,
+                versions,
+                downloads_delta,
+                ..
+            } => {
+                summary.updated += 1;
+                summary.new_flavors += added_flavors.len();
+                summary.version_bumps += versions.len();
+                summary.downloads_delta += downloads_delta;
+            }
+        }
+        diff.changes.push(change);
+    }
+
+    diff
+}
+
+fn join_flavors(flavors: &[Flavor]) -> String {
+    flavors
+        .iter()
+        .map(|f| f.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in self.changes.iter() {
+            match change {
+                Change::Added {
+                    source,
+                    id,
+                    name,
+                    flavors,
+                } => writeln!(
+                    f,
+                    "+ {} {} {} [{}]",
+                    source,
+                    id,
+                    name,
+                    join_flavors(flavors)
+                )?,
+                Change::Removed { source, id, name } => {
+                    writeln!(f, "- {} {} {}", source, id, name)?
+                }
+                Change::Updated {
+                    source,
+                    id,
+                    name,
+                    added_flavors,
+                    removed_flavors,
+                    versions,
+                    downloads_delta,
+                } => {
+                    let mut details = vec![];
+                    if !added_flavors.is_empty() {
+                        details.push(format!("added {}", join_flavors(added_flavors)));
+                    }
+                    if !removed_flavors.is_empty() {
+                        details.push(format!("removed {}", join_flavors(removed_flavors)));
+                    }
+                    for version in versions {
+                        let mut detail = format!(
+                            "{} {} -> {}",
+                            version.flavor,
+                            version.old_game_version.as_deref().unwrap_or("?"),
+                            version.new_game_version.as_deref().unwrap_or("?"),
+                        );
+                        if version.old_addon_version != version.new_addon_version {
+                            detail.push_str(&format!(
+                                " (addon {} -> {})",
+                                version.old_addon_version.as_deref().unwrap_or("?"),
+                                version.new_addon_version.as_deref().unwrap_or("?"),
+                            ));
+                        }
+                        details.push(detail);
+                    }
+                    if *downloads_delta != 0 {
+                        details.push(format!("downloads {:+}", downloads_delta));
+                    }
+                    writeln!(f, "~ {} {} {}: {}", source, id, name, details.join("; "))?
+                }
+            }
+        }
+
+        let summary = &self.summary;
+        write!(
+            f,
+            "{} added, {} removed, {} updated ({} new flavors, {} version bumps, {:+} downloads)",
+            summary.added,
+            summary.removed,
+            summary.updated,
+            summary.new_flavors,
+            summary.version_bumps,
+            summary.downloads_delta
+        )
+    }
+}
+
+/// Output format for `catalog diff`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Human,
+    Json,
+    Ndjson,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Format::Human),
+            "json" => Ok(Format::Json),
+            "ndjson" => Ok(Format::Ndjson),
+            _ => Err(format!("unknown format {}", s)),
+        }
+    }
+}
+
+#[test]
+fn test_diff() {
+    let version = |flavor, game_version: &str| Version {
+        flavor,
+        game_version: Some(game_version.to_owned()),
+        addon_version: None,
+        date: None,
+        download_url: None,
+        file_name: None,
+        file_id: None,
+        folders: vec![],
+    };
+    let addon = |source, id, downloads, versions| Addon {
+        id,
+        name: format!("Addon {}", id),
+        url: String::new(),
+        number_of_downloads: downloads,
+        summary: String::new(),
+        versions,
+        categories: vec![],
+        authors: vec![],
+        thumbnail_url: None,
+        screenshot_urls: vec![],
+        source,
+        project_id: None,
+    };
+
+    let old = vec![
+        addon(Source::Curse, 1, 10, vec![version(Flavor::Retail, "9.1.5")]),
+        addon(Source::Curse, 2, 10, vec![version(Flavor::Retail, "9.1.5")]),
+        addon(Source::Tukui, 1, 10, vec![version(Flavor::Retail, "9.1.5")]),
+    ];
+    let new = vec![
+        addon(
+            Source::Curse,
+            1,
+            15,
+            vec![
+                version(Flavor::Retail, "9.2.0"),
+                version(Flavor::ClassicWotlk, "3.4.0"),
+            ],
+        ),
+        addon(Source::Tukui, 1, 10, vec![version(Flavor::Retail, "9.1.5")]),
+        addon(Source::Hub, 1, 0, vec![version(Flavor::Retail, "9.1.5")]),
+    ];
+
+    let diff = diff(&old, &new);
+    assert_eq!(
+        diff.summary,
+        Summary {
+            added: 1,
+            removed: 1,
+            updated: 1,
+            new_flavors: 1,
+            version_bumps: 1,
+            downloads_delta: 5,
+        }
+    );
+    assert_eq!(
+        diff.changes[0],
+        Change::Updated {
+            source: Source::Curse,
+            id: 1,
+            name: "Addon 1".to_owned(),
+            added_flavors: vec![Flavor::ClassicWotlk],
+            removed_flavors: vec![],
+            versions: vec![VersionChange {
+                flavor: Flavor::Retail,
+                old_game_version: Some("9.1.5".to_owned()),
+                new_game_version: Some("9.2.0".to_owned()),
+                old_addon_version: None,
+                new_addon_version: None,
+                old_date: None,
+                new_date: None,
+            }],
+            downloads_delta: 5,
+        }
+    );
+    assert!(matches!(diff.changes[1], Change::Removed { id: 2, .. }));
+    assert!(matches!(
+        diff.changes[2],
+        Change::Added {
+            source: Source::Hub,
+            ..
+        }
+    ));
+}
@@ -1,52 +1,494 @@
+use chrono::Utc;
+use compress::{Artifact, Compression, Manifest};
 use core::{
-    backend::{Backend, Source::*},
+    backend::{Addon, Backend, Source, Source::*},
+    catalog::{self, Catalog, Envelope, SourceStats, SCHEMA_VERSION},
+    config::Config,
     error::Error,
+    matching,
 };
-use futures::{executor::block_on, try_join};
+use futures::{executor::block_on, future::join_all};
+use output::Output;
+use std::collections::BTreeMap;
 use std::fs::File;
-use std::io::Write;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use structopt::StructOpt;
+use validate::Severity;
 
-const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
+mod compress;
+mod diff;
+mod output;
+mod validate;
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 fn main() {
     let future = handle_opts();
-    let _ = block_on(future);
+    if let Err(error) = block_on(future) {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
 }
 
 async fn handle_opts() -> Result<(), Error> {
     let opts = Opts::from_args();
+    let config = opts.config.into_config()?;
     match opts.command {
         // Generate a JSON file with all backend sources combined.
-        Command::Catalog => {
-            let (tukui, wowi, curse, hub) = try_join!(
-                Tukui.get_addons(),
-                WowI.get_addons(),
-                Curse.get_addons(),
-                Hub.get_addons()
-            )?;
-            // Combine all addons.
-            let concatenated = [&tukui[..], &wowi[..], &curse[..], &hub[..]].concat();
-            // Serialize.
-            let json = serde_json::to_string(&concatenated)?;
-            // Create catalog file.
-            let file_name = format!("catalog-{}.json", VERSION.expect("no version was found"));
-            let mut file = File::create(file_name)?;
-            // Write to file.
-            file.write_all(json.as_bytes())?;
+        Command::Catalog {
+            partial,
+            require,
+            since,
+            incremental,
+            output,
+            compress,
+            envelope,
+        } => {
+            let mut sources = vec![Tukui, WowI, Curse, Hub, Wago, GitHub];
+            // Wago is opt-in: without an API key it is left out, unless it
+            // is required.
+            if config.api_keys.wago.is_none() && !require.contains(&Wago) {
+                eprintln!("skipping {}: no API key", Wago);
+                sources.retain(|source| *source != Wago);
+            }
+            // So is GitHub, without a list of repositories.
+            if config.github_repositories.is_none() && !require.contains(&GitHub) {
+                eprintln!("skipping {}: no repository list", GitHub);
+                sources.retain(|source| *source != GitHub);
+            }
+            let output = Output::from_template(&output, VERSION, Utc::now());
+            if !compress.is_empty() && output == Output::Stdout {
+                return Err(Error::Io(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "--compress requires the catalog to be written to a file",
+                )));
+            }
+
+            // Fail early, rather than after crawling every other source.
+            let api_keys = [
+                (Curse, &config.api_keys.curse),
+                (Wago, &config.api_keys.wago),
+            ];
+            for (source, api_key) in api_keys {
+                if !sources.contains(&source) {
+                    continue;
+                }
+                let required = !partial || require.contains(&source);
+                if required && api_key.is_none() {
+                    return Err(Error::MissingApiKey(source));
+                }
+            }
+            if sources.contains(&GitHub) && config.github_repositories.is_none() {
+                return Err(Error::MissingRepositories(GitHub));
+            }
+
+            // Previous catalog used to only fetch what changed.
+            let baseline_path = match since {
+                Some(path) => Some(path),
+                None if incremental => output
+                    .path()
+                    .filter(|path| path.exists())
+                    .map(Path::to_path_buf),
+                None => None,
+            };
+            let baseline = match &baseline_path {
+                Some(path) => Some(read_catalog(path)?),
+                None => None,
+            };
+            // Only an envelope says when sources were fetched.
+            if let (Some(path), Some(baseline)) = (&baseline_path, &baseline) {
+                if sources.contains(&Curse) && baseline.fetched_at(Curse).is_none() {
+                    eprintln!(
+                        "warning: {} does not say when {} was fetched, fetching all of it. \
+                         Write the catalog with --envelope to refresh it next time.",
+                        path.display(),
+                        Curse
+                    );
+                }
+            }
+
+            let results = join_all(sources.iter().map(|source| {
+                let config = &config;
+                let baseline = baseline.as_ref();
+                async move {
+                    let result = match baseline {
+                        Some(baseline) => {
+                            let fetched_at = baseline.fetched_at(*source);
+                            source
+                                .get_addons_since(config, baseline.addons(), fetched_at)
+                                .await
+                        }
+                        None => source.get_addons(config).await,
+                    };
+                    (result, Utc::now())
+                }
+            }))
+            .await;
+
+            // Combine all addons, keeping track of the sources which failed.
+            let mut concatenated: Vec<Addon> = vec![];
+            let mut failures: Vec<(Source, Error)> = vec![];
+            let mut stats: BTreeMap<Source, SourceStats> = BTreeMap::new();
+            for (source, (result, fetched_at)) in sources.iter().zip(results) {
+                let stat = match result {
+                    Ok(addons) => {
+                        let count = addons.len();
+                        concatenated.extend(addons);
+                        SourceStats {
+                            count,
+                            fetched_at,
+                            ok: true,
+                            error: None,
+                        }
+                    }
+                    Err(error) => {
+                        let stat = SourceStats {
+                            count: 0,
+                            fetched_at,
+                            ok: false,
+                            error: Some(error.to_string()),
+                        };
+                        failures.push((*source, error));
+                        stat
+                    }
+                };
+                stats.insert(*source, stat);
+            }
+
+            if let Some(error) = check_failures(&failures, partial, &require) {
+                return Err(error);
+            }
+
+            // Sort, so unchanged addons give an unchanged catalog.
+            catalog::normalize(&mut concatenated);
+            // Link addons which are the same project on different sources.
+            matching::assign_projects(&mut concatenated);
+            // Serialize, optionally wrapped in an envelope.
+            let json = if envelope {
+                serde_json::to_string(&Envelope {
+                    schema_version: SCHEMA_VERSION,
+                    generated_at: Utc::now(),
+                    tool_version: VERSION.to_owned(),
+                    sources: stats,
+                    addons: concatenated,
+                })?
+            } else {
+                serde_json::to_string(&concatenated)?
+            };
+            // Write to file, or stdout.
+            output.write(json.as_bytes())?;
+
+            // Write pre-compressed siblings and a manifest describing them.
+            if let Some(path) = output.path().filter(|_| !compress.is_empty()) {
+                write_compressed(path, json.as_bytes(), &compress)?;
+            }
+            Ok(())
+        }
+        // Compare two catalog files.
+        Command::Diff { old, new, format } => {
+            let old = read_catalog(&old)?.into_addons();
+            let new = read_catalog(&new)?.into_addons();
+            let diff = diff::diff(&old, &new);
+            match format {
+                diff::Format::Human => println!("{}", diff),
+                diff::Format::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+                diff::Format::Ndjson => {
+                    for change in diff.changes.iter() {
+                        println!("{}", serde_json::to_string(change)?);
+                    }
+                }
+            }
             Ok(())
         }
+        // Lint a catalog file.
+        Command::Validate { file, strict } => {
+            let file = File::open(file)?;
+            let catalog: serde_json::Value = serde_json::from_reader(io::BufReader::new(file))?;
+            let problems = validate::validate(&catalog);
+            for problem in problems.iter() {
+                println!("{}", problem);
+            }
+
+            let count = |severity| problems.iter().filter(|p| p.severity == severity).count();
+            let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+            println!("{} errors, {} warnings", errors, warnings);
+            if errors > 0 || (strict && warnings > 0) {
+                return Err(Error::Io(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "catalog is not valid",
+                )));
+            }
+            Ok(())
+        }
+        // Print the JSON Schema of the catalog format.
+        Command::Schema => {
+            let schema = serde_json::to_string_pretty(&Catalog::schema())?;
+            println!("{}", schema);
+            Ok(())
+        }
+    }
+}
+
+/// Reads a catalog file, either a bare array of addons or an envelope.
+///
+/// Older catalogs can contain entries this version no longer understands, eg.
+/// removed sources. These are reported and skipped.
+fn read_catalog(path: &Path) -> Result<Catalog, Error> {
+    let file = File::open(path)?;
+    let value: serde_json::Value = serde_json::from_reader(io::BufReader::new(file))?;
+    let (values, envelope): (Vec<serde_json::Value>, _) = match value {
+        serde_json::Value::Object(mut envelope) => {
+            let values = serde_json::from_value(envelope.remove("addons").unwrap_or_default())?;
+            (values, Some(envelope))
+        }
+        value => (serde_json::from_value(value)?, None),
+    };
+    let mut addons = Vec::with_capacity(values.len());
+    for (index, value) in values.into_iter().enumerate() {
+        match serde_json::from_value(value) {
+            Ok(addon) => addons.push(addon),
+            Err(error) => eprintln!("{}: skipping entry {}: {}", path.display(), index, error),
+        }
+    }
+    // Without readable metadata the catalog is treated as a bare array, so
+    // it is not trusted for when sources were fetched.
+    let envelope = envelope.and_then(|mut envelope| {
+        envelope.insert("addons".to_owned(), serde_json::Value::Array(vec![]));
+        serde_json::from_value::<Envelope>(serde_json::Value::Object(envelope)).ok()
+    });
+    Ok(match envelope {
+        Some(envelope) => Catalog::Envelope(Envelope { addons, ..envelope }),
+        None => Catalog::Addons(addons),
+    })
+}
+
+/// Writes `bytes` compressed with each of `compressions` next to `path`, eg.
+/// `catalog.json.gz`, along with a `catalog.manifest.json` listing the size
+/// and SHA-256 of every artifact.
+fn write_compressed(path: &Path, bytes: &[u8], compressions: &[Compression]) -> io::Result<()> {
+    let file_name = |path: &Path| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+
+    let mut artifacts = vec![Artifact::new(file_name(path), None, bytes)];
+    for compression in compressions {
+        let compressed = compression.compress(bytes)?;
+        let mut compressed_path = path.as_os_str().to_owned();
+        compressed_path.push(".");
+        compressed_path.push(compression.extension());
+        let compressed_path = PathBuf::from(compressed_path);
+
+        output::write_atomic(&compressed_path, &compressed)?;
+        artifacts.push(Artifact::new(
+            file_name(&compressed_path),
+            Some(compression.content_encoding()),
+            &compressed,
+        ));
+    }
+
+    let manifest = serde_json::to_vec_pretty(&Manifest { artifacts })?;
+    output::write_atomic(&path.with_extension("manifest.json"), &manifest)
+}
+
+/// Reports every failed source and returns an `Error` if the catalog should
+/// not be written.
+///
+/// Without `partial` every source is required. With `partial` only failures
+/// of `required` sources are fatal.
+fn check_failures(
+    failures: &[(Source, Error)],
+    partial: bool,
+    required: &[Source],
+) -> Option<Error> {
+    for (source, error) in failures.iter() {
+        eprintln!("failed to fetch addons from {}: {}", source, error);
+    }
+
+    let fatal = failures
+        .iter()
+        .map(|(source, _)| *source)
+        .filter(|source| !partial || required.contains(source))
+        .collect::<Vec<Source>>();
+    if fatal.is_empty() {
+        None
+    } else {
+        Some(Error::RequiredSourcesFailed(fatal))
     }
 }
 
 #[derive(Debug, StructOpt)]
 #[structopt()]
 struct Opts {
+    #[structopt(flatten)]
+    config: ConfigOpts,
     #[structopt(subcommand)]
     command: Command,
 }
 
+/// Options which override the backend `Config`.
+///
+/// Flags take precedence over environment variables, which take precedence
+/// over the config file.
+#[derive(Debug, StructOpt)]
+struct ConfigOpts {
+    /// Path to a TOML config file.
+    #[structopt(long, env = "CATALOG_CONFIG", parse(from_os_str))]
+    config: Option<PathBuf>,
+    /// Base url for Curse.
+    #[structopt(long, env = "CATALOG_CURSE_BASE_URL")]
+    curse_base_url: Option<String>,
+    /// Base url for Tukui.
+    #[structopt(long, env = "CATALOG_TUKUI_BASE_URL")]
+    tukui_base_url: Option<String>,
+    /// Base url for WowInterface.
+    #[structopt(long, env = "CATALOG_WOWI_BASE_URL")]
+    wowi_base_url: Option<String>,
+    /// Base url for Hub.
+    #[structopt(long, env = "CATALOG_HUB_BASE_URL")]
+    hub_base_url: Option<String>,
+    /// Base url for Wago.
+    #[structopt(long, env = "CATALOG_WAGO_BASE_URL")]
+    wago_base_url: Option<String>,
+    /// Base url for the GitHub API.
+    #[structopt(long, env = "CATALOG_GITHUB_BASE_URL")]
+    github_base_url: Option<String>,
+    /// File listing the GitHub repositories to include, one `owner/name` per
+    /// line.
+    #[structopt(long, env = "CATALOG_GITHUB_REPOSITORIES", parse(from_os_str))]
+    github_repositories: Option<PathBuf>,
+    /// Path to a file containing the Curse API key. Takes precedence over
+    /// `CURSE_API_KEY`.
+    #[structopt(long, parse(from_os_str))]
+    curse_api_key_file: Option<PathBuf>,
+    /// Path to a file containing the Wago API key. Takes precedence over
+    /// `WAGO_API_KEY`.
+    #[structopt(long, parse(from_os_str))]
+    wago_api_key_file: Option<PathBuf>,
+    /// Path to a file containing a GitHub token. Takes precedence over
+    /// `GITHUB_TOKEN`.
+    #[structopt(long, parse(from_os_str))]
+    github_token_file: Option<PathBuf>,
+    /// Total number of attempts for a request, including the first one.
+    #[structopt(long, env = "CATALOG_RETRY_MAX_ATTEMPTS")]
+    retry_max_attempts: Option<u32>,
+    /// Delay in milliseconds before the first retry of a request.
+    #[structopt(long, env = "CATALOG_RETRY_BASE_DELAY_MS")]
+    retry_base_delay_ms: Option<u64>,
+    /// Upper bound in milliseconds for a single delay between retries.
+    #[structopt(long, env = "CATALOG_RETRY_MAX_DELAY_MS")]
+    retry_max_delay_ms: Option<u64>,
+}
+
+impl ConfigOpts {
+    fn into_config(self) -> Result<Config, Error> {
+        let mut config = match self.config {
+            Some(path) => Config::load(path)?,
+            None => Config::default(),
+        };
+
+        let base_urls = &mut config.base_urls;
+        let overrides = [
+            (&mut base_urls.curse, self.curse_base_url),
+            (&mut base_urls.tukui, self.tukui_base_url),
+            (&mut base_urls.wowi, self.wowi_base_url),
+            (&mut base_urls.hub, self.hub_base_url),
+            (&mut base_urls.wago, self.wago_base_url),
+            (&mut base_urls.github, self.github_base_url),
+        ];
+        for (base_url, value) in overrides {
+            if let Some(value) = value {
+                *base_url = value;
+            }
+        }
+
+        if let Some(path) = self.github_repositories {
+            config.github_repositories = Some(path);
+        }
+
+        if let Some(max_attempts) = self.retry_max_attempts {
+            config.retry.max_attempts = max_attempts;
+        }
+        if let Some(millis) = self.retry_base_delay_ms {
+            config.retry.base_delay = Duration::from_millis(millis);
+        }
+        if let Some(millis) = self.retry_max_delay_ms {
+            config.retry.max_delay = Duration::from_millis(millis);
+        }
+
+        let api_keys = &mut config.api_keys;
+        let key_sources = [
+            (
+                &mut api_keys.curse,
+                self.curse_api_key_file,
+                "CURSE_API_KEY",
+            ),
+            (&mut api_keys.wago, self.wago_api_key_file, "WAGO_API_KEY"),
+            (&mut api_keys.github, self.github_token_file, "GITHUB_TOKEN"),
+        ];
+        for (api_key, file, env) in key_sources {
+            if let Some(path) = file {
+                *api_key = Some(std::fs::read_to_string(path)?.trim().to_owned());
+            } else if let Ok(value) = std::env::var(env) {
+                *api_key = Some(value);
+            }
+            *api_key = api_key.take().filter(|key| !key.is_empty());
+        }
+
+        Ok(config)
+    }
+}
+
 #[derive(Debug, StructOpt)]
 enum Command {
-    Catalog,
+    Catalog {
+        /// Write the catalog with the sources that succeeded, even if others failed.
+        #[structopt(long)]
+        partial: bool,
+        /// Sources which must succeed for the catalog to be written, eg.
+        /// `curse,tukui`. Requires `--partial`.
+        #[structopt(long, use_delimiter = true, requires = "partial")]
+        require: Vec<Source>,
+        /// Previous catalog file. Only addons which changed since are fetched,
+        /// where the source supports it.
+        #[structopt(long, parse(from_os_str))]
+        since: Option<PathBuf>,
+        /// Like `--since`, using the existing catalog file if there is one.
+        #[structopt(long, conflicts_with = "since")]
+        incremental: bool,
+        /// Path to write the catalog to, or `-` for stdout. `{version}`,
+        /// `{date}` and `{timestamp}` are replaced.
+        #[structopt(short, long, default_value = "catalog-{version}.json")]
+        output: String,
+        /// Also write a compressed copy: `gzip`, `br` or `zstd`. Can be repeated.
+        #[structopt(long, number_of_values = 1)]
+        compress: Vec<Compression>,
+        /// Wrap the addons in an object with the schema version, generation
+        /// time and per-source stats.
+        #[structopt(long)]
+        envelope: bool,
+    },
+    /// Shows what changed between two catalog files.
+    Diff {
+        #[structopt(parse(from_os_str))]
+        old: PathBuf,
+        #[structopt(parse(from_os_str))]
+        new: PathBuf,
+        /// Output format: `human`, `json` or `ndjson`.
+        #[structopt(long, default_value = "human")]
+        format: diff::Format,
+    },
+    /// Prints the JSON Schema of the catalog format.
+    Schema,
+    /// Checks a catalog file for problems. Exits non-zero if there are errors.
+    Validate {
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+        /// Treat warnings as errors.
+        #[structopt(long)]
+        strict: bool,
+    },
 }
@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Where the catalog is written.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Output {
+    Stdout,
+    File(PathBuf),
+}
+
+impl Output {
+    /// Resolves an `--output` value. `-` means stdout, otherwise `{version}`,
+    /// `{date}` (`YYYY-MM-DD`) and `{timestamp}` (unix seconds) are replaced.
+    pub fn from_template(template: &str, version: &str, now: DateTime<Utc>) -> Output {
+        if template == "-" {
+            return Output::Stdout;
+        }
+        let path = template
+            .replace("{version}", version)
+            .replace("{date}", &now.format("%Y-%m-%d").to_string())
+            .replace("{timestamp}", &now.timestamp().to_string());
+        Output::File(PathBuf::from(path))
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Output::Stdout => None,
+            Output::File(path) => Some(path),
+        }
+    }
+
+    pub fn write(&self, bytes: &[u8]) -> io::Result<()> {
+        match self {
+            Output::Stdout => {
+                let stdout = io::stdout();
+                let mut stdout = stdout.lock();
+                stdout.write_all(bytes)?;
+                stdout.flush()
+            }
+            Output::File(path) => write_atomic(path, bytes),
+        }
+    }
+}
+
+/// Writes `bytes` to a temporary file next to `path` and renames it into
+/// place, so readers never see a partially written file.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "output is not a file"))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = File::create(&temp_path).and_then(|mut file| {
+        file.write_all(bytes)?;
+        file.sync_all()
+    });
+    match result.and_then(|_| fs::rename(&temp_path, path)) {
+        Ok(()) => Ok(()),
+        Err(error) => {
+            let _ = fs::remove_file(&temp_path);
+            Err(error)
+        }
+    }
+}
+
+#[test]
+fn test_output_template() {
+    use chrono::TimeZone;
+
+    let now = Utc.with_ymd_and_hms(2021, 11, 5, 12, 0, 0).unwrap();
+    assert_eq!(Output::from_template("-", "0.2.0", now), Output::Stdout);
+    assert_eq!(
+        Output::from_template("out/catalog-{version}-{date}.json", "0.2.0", now),
+        Output::File(PathBuf::from("out/catalog-0.2.0-2021-11-05.json"))
+    );
+    assert_eq!(
+        Output::from_template("catalog-{timestamp}.json", "0.2.0", now),
+        Output::File(PathBuf::from("catalog-1636113600.json"))
+    );
+}
+
+#[test]
+fn test_write_atomic() {
+    let dir = std::env::temp_dir().join(format!("catalog-output-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("catalog.json");
+
+    write_atomic(&path, b"[]").unwrap();
+    write_atomic(&path, b"[{}]").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "[{}]");
+    // Only the catalog itself is left behind.
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
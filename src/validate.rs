@@ -0,0 +1,242 @@
+use core::backend::{Flavor, Source};
+use core::utility::parse_date;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A problem found in a single catalog entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub severity: Severity,
+    /// Position of the entry in the catalog.
+    pub index: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: entry {}: {}", severity, self.index, self.message)
+    }
+}
+
+fn is_valid_url(url: &str) -> bool {
+    match Url::parse(url) {
+        Ok(url) => matches!(url.scheme(), "http" | "https") && url.host().is_some(),
+        Err(_) => false,
+    }
+}
+
+/// Checks every entry of a catalog, either a bare array or an envelope.
+///
+/// Entries are checked as plain JSON, so entries which would not deserialize
+/// to `Addon` are reported rather than aborting the check.
+pub fn validate(catalog: &Value) -> Vec<Problem> {
+    let entries = match catalog {
+        Value::Array(entries) => entries,
+        Value::Object(envelope) => match envelope.get("addons") {
+            Some(Value::Array(entries)) => entries,
+            _ => {
+                return vec![Problem {
+                    severity: Severity::Error,
+                    index: 0,
+                    message: "envelope has no addons array".to_owned(),
+                }]
+            }
+        },
+        _ => {
+            return vec![Problem {
+                severity: Severity::Error,
+                index: 0,
+                message: "catalog is neither an array nor an envelope".to_owned(),
+            }]
+        }
+    };
+
+    let mut problems = vec![];
+    // Flavors seen for each `(source, id)`. Only entries sharing a flavor are
+    // duplicates.
+    let mut seen: HashMap<(String, i64), Vec<(usize, String)>> = HashMap::new();
+
+    for (index, entry) in entries.iter().enumerate() {
+        let mut problem = |severity, message: String| {
+            problems.push(Problem {
+                severity,
+                index,
+                message,
+            })
+        };
+
+        let source = entry["source"].as_str().unwrap_or_default();
+        let id = entry["id"].as_i64();
+        let label = format!(
+            "{} {}",
+            source,
+            id.map(|id| id.to_string()).unwrap_or_default()
+        );
+
+        if serde_json::from_value::<Source>(entry["source"].clone()).is_err() {
+            problem(Severity::Error, format!("{}: unknown source", label));
+        }
+        if id.is_none() {
+            problem(Severity::Error, format!("{}: missing or invalid id", label));
+        }
+        if entry["name"].as_str().is_none_or(|n| n.trim().is_empty()) {
+            problem(Severity::Error, format!("{}: empty name", label));
+        }
+        match entry["url"].as_str() {
+            None | Some("") => problem(Severity::Error, format!("{}: empty url", label)),
+            Some(url) if !is_valid_url(url) => problem(
+                Severity::Error,
+                format!("{}: malformed url {:?}", label, url),
+            ),
+            _ => {}
+        }
+
+        let versions = entry["versions"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        if versions.is_empty() {
+            problem(Severity::Warning, format!("{}: no versions", label));
+        }
+        let mut flavors = vec![];
+        for version in versions {
+            let flavor = &version["flavor"];
+            match serde_json::from_value::<Flavor>(flavor.clone()) {
+                Ok(parsed) => flavors.push(parsed.to_string()),
+                Err(_) => problem(
+                    Severity::Error,
+                    format!("{}: unknown flavor {}", label, flavor),
+                ),
+            }
+            if version["game_version"].as_str().map(str::trim) == Some("") {
+                problem(
+                    Severity::Warning,
+                    format!("{}: empty game_version for {}", label, flavor),
+                );
+            }
+            match &version["date"] {
+                Value::Null => {}
+                Value::String(date) if parse_date(date).is_some() => {}
+                date => problem(
+                    Severity::Error,
+                    format!("{}: unparseable date {} for {}", label, date, flavor),
+                ),
+            }
+        }
+
+        if let Some(id) = id {
+            let others = seen.entry((source.to_owned(), id)).or_default();
+            for (other, flavor) in others.iter() {
+                if flavors.contains(flavor) {
+                    problem(
+                        Severity::Error,
+                        format!("{}: duplicate of entry {} for {}", label, other, flavor),
+                    );
+                }
+            }
+            others.extend(flavors.into_iter().map(|flavor| (index, flavor)));
+        }
+    }
+
+    problems
+}
+
+#[test]
+fn test_validate() {
+    let catalog = serde_json::json!([
+        {
+            "id": 1,
+            "name": "WeakAuras",
+            "url": "https://www.curseforge.com/wow/addons/weakauras-2",
+            "versions": [{ "flavor": "Retail", "game_version": "9.1.5", "date": "2021-11-03T16:24:03Z" }],
+            "source": "Curse"
+        },
+        {
+            "id": 1,
+            "name": " ",
+            "url": "not a url",
+            "versions": [{ "flavor": "Retail", "game_version": "", "date": "yesterday" }],
+            "source": "Curse"
+        },
+        {
+            "id": 2,
+            "name": "AlhanaUI",
+            "url": "",
+            "versions": [{ "flavor": "Dragonflight", "game_version": null, "date": "2019-07-25 17:00:42" }],
+            "source": "Tukui"
+        },
+        {
+            "id": 2,
+            "name": "ElvUI",
+            "url": "https://www.tukui.org/classic-addons.php?id=2",
+            "versions": [{ "flavor": "ClassicEra", "game_version": "1.14.0", "date": null }],
+            "source": "Tukui"
+        },
+        {
+            "id": 3,
+            "name": "NoRelease",
+            "url": "https://github.com/someone/norelease",
+            "versions": [],
+            "source": "TownlongYak"
+        }
+    ]);
+
+    let problems = validate(&catalog)
+        .into_iter()
+        .map(|p| (p.index, p.severity, p.message))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        problems,
+        vec![
+            (1, Severity::Error, "Curse 1: empty name".to_owned()),
+            (
+                1,
+                Severity::Error,
+                "Curse 1: malformed url \"not a url\"".to_owned()
+            ),
+            (
+                1,
+                Severity::Warning,
+                "Curse 1: empty game_version for \"Retail\"".to_owned()
+            ),
+            (
+                1,
+                Severity::Error,
+                "Curse 1: unparseable date \"yesterday\" for \"Retail\"".to_owned()
+            ),
+            (
+                1,
+                Severity::Error,
+                "Curse 1: duplicate of entry 0 for retail".to_owned()
+            ),
+            (2, Severity::Error, "Tukui 2: empty url".to_owned()),
+            (
+                2,
+                Severity::Error,
+                "Tukui 2: unknown flavor \"Dragonflight\"".to_owned()
+            ),
+            (
+                4,
+                Severity::Error,
+                "TownlongYak 3: unknown source".to_owned()
+            ),
+            (
+                4,
+                Severity::Warning,
+                "TownlongYak 3: no versions".to_owned()
+            ),
+        ]
+    );
+}